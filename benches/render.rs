@@ -0,0 +1,86 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use oam::{flavour::get_flavour_config, ir::Ir, renderer::Renderer, schema::OpenAPI};
+use std::{env, fs};
+
+/// Number of component schemas in the synthetic spec.
+const SCHEMAS: usize = 500;
+
+const CONFIG: &str = r#"
+language = "rust"
+
+[[template]]
+input = "model"
+iteration = "ir.types"
+output = "src/models/{entity}.rs"
+"#;
+
+const MODEL: &str = r#"
+{%- if item.schema.kind == "object" -%}
+pub struct {{ entity | type_name }} {
+{%- for property in item.schema.properties %}
+    /// {{ property.description | default(value="") }}
+    pub {{ property.name | snake_case | escape_reserved }}: {{ property.schema | type }},
+{%- endfor %}
+}
+{%- else -%}
+pub type {{ entity | type_name }} = {{ item.schema | type }};
+{%- endif %}
+"#;
+
+/// A spec of objects with a mix of primitive, array and reference properties.
+fn spec() -> String {
+    let mut spec = String::from(
+        "openapi: 3.1.0\ninfo:\n  title: Bench\n  version: 1.0.0\npaths: {}\ncomponents:\n  schemas:\n",
+    );
+    for index in 0..SCHEMAS {
+        spec.push_str(&format!(
+            "    Model{index}:\n      type: object\n      properties:\n"
+        ));
+        for property in 0..10 {
+            spec.push_str(&format!(
+                "        field{property}:\n          type: string\n          description: Field {property}\n"
+            ));
+        }
+        spec.push_str(
+            "        tags:\n          type: array\n          items:\n            type: string\n",
+        );
+        if index > 0 {
+            spec.push_str(&format!(
+                "        parent:\n          $ref: '#/components/schemas/Model{}'\n",
+                index - 1
+            ));
+        }
+    }
+    spec
+}
+
+fn render(c: &mut Criterion) {
+    let project = env::temp_dir().join("oam-bench");
+    fs::create_dir_all(project.join(".openapi/flavours/bench")).unwrap();
+    fs::write(project.join(".openapi/flavours/bench/config.toml"), CONFIG).unwrap();
+    fs::write(project.join(".openapi/flavours/bench/model.tera"), MODEL).unwrap();
+    fs::write(project.join("openapi.yaml"), spec()).unwrap();
+    env::set_current_dir(&project).unwrap();
+
+    let document = OpenAPI::from("openapi.yaml").unwrap();
+    let ir = Ir::build(&document).unwrap();
+    let flavour = get_flavour_config(String::from("bench")).unwrap();
+    let renderer = Renderer::new(&flavour, Default::default()).unwrap();
+
+    let mut group = c.benchmark_group("render");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        b.iter(|| pool.install(|| renderer.render(&flavour, &document, &ir, None).unwrap()))
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| renderer.render(&flavour, &document, &ir, None).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,286 @@
+/// Splits a name into words on separators, case changes and acronym boundaries,
+/// e.g. `HTTPServer2` into `HTTP` and `Server2` or `x-next` into `x` and `next`.
+/// Digits stick to the word before them.
+pub fn words(value: &str) -> Vec<String> {
+    let characters: Vec<char> = value.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for (index, &character) in characters.iter().enumerate() {
+        if !character.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if let Some(previous) = current.chars().last() {
+            let next = characters.get(index + 1);
+            let boundary = character.is_uppercase()
+                && (previous.is_lowercase()
+                    || previous.is_numeric()
+                    || (previous.is_uppercase() && next.is_some_and(|next| next.is_lowercase())));
+            if boundary {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(character);
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// `HTTPServer2` becomes `HttpServer2`.
+pub fn pascal_case(value: &str) -> String {
+    words(value).iter().map(|word| capitalize(word)).collect()
+}
+
+/// `HTTPServer2` becomes `httpServer2`.
+pub fn camel_case(value: &str) -> String {
+    words(value)
+        .iter()
+        .enumerate()
+        .map(|(index, word)| match index {
+            0 => word.to_lowercase(),
+            _ => capitalize(word),
+        })
+        .collect()
+}
+
+/// `HTTPServer2` becomes `http_server2`.
+pub fn snake_case(value: &str) -> String {
+    join(value, "_").to_lowercase()
+}
+
+/// `HTTPServer2` becomes `HTTP_SERVER2`.
+pub fn screaming_snake_case(value: &str) -> String {
+    join(value, "_").to_uppercase()
+}
+
+/// `HTTPServer2` becomes `http-server2`.
+pub fn kebab_case(value: &str) -> String {
+    join(value, "-").to_lowercase()
+}
+
+/// Pluralizes the last word of a name, e.g. `PetCategory` becomes `PetCategories`.
+pub fn pluralize(value: &str) -> String {
+    if !value.is_ascii() {
+        return format!("{value}s");
+    }
+
+    let lowercase = value.to_lowercase();
+    for (singular, plural) in IRREGULAR {
+        if let Some(stem) = lowercase.strip_suffix(singular) {
+            return format!(
+                "{}{}",
+                &value[..stem.len()],
+                match_case(&value[stem.len()..], plural)
+            );
+        }
+    }
+
+    let ends_with_consonant_y = lowercase.ends_with('y')
+        && !lowercase[..lowercase.len() - 1].ends_with(['a', 'e', 'i', 'o', 'u']);
+    if ends_with_consonant_y {
+        let stem = &value[..value.len() - 1];
+        return format!("{stem}{}", match_case(&value[value.len() - 1..], "ies"));
+    }
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|suffix| lowercase.ends_with(suffix))
+    {
+        return format!("{value}{}", match_case(&value[value.len() - 1..], "es"));
+    }
+    if value.is_empty() {
+        return String::new();
+    }
+    format!("{value}{}", match_case(&value[value.len() - 1..], "s"))
+}
+
+/// Singularizes the last word of a name, e.g. `PetCategories` becomes `PetCategory`.
+pub fn singularize(value: &str) -> String {
+    if !value.is_ascii() {
+        return value.to_string();
+    }
+
+    let lowercase = value.to_lowercase();
+    for (singular, plural) in IRREGULAR {
+        if let Some(stem) = lowercase.strip_suffix(plural) {
+            return format!(
+                "{}{}",
+                &value[..stem.len()],
+                match_case(&value[stem.len()..], singular)
+            );
+        }
+    }
+
+    // Words ending in `ie`, such as `Movie`, are irregular, and short ones such as `Pie` end in `s`.
+    if lowercase.ends_with("ies") && lowercase.len() > 4 {
+        let stem = &value[..value.len() - 3];
+        return format!("{stem}{}", match_case(&value[value.len() - 3..], "y"));
+    }
+    // `es` follows sibilants, e.g. `Boxes` or `Statuses`, but not in `Responses` or `Houses`.
+    if let Some(stem) = lowercase.strip_suffix("es") {
+        let sibilant = ["ss", "x", "zz", "ch", "sh"]
+            .iter()
+            .any(|suffix| stem.ends_with(suffix))
+            || stem
+                .strip_suffix("us")
+                .is_some_and(|rest| rest.ends_with(|character: char| !"aeiou".contains(character)));
+        if sibilant {
+            return value[..value.len() - 2].to_string();
+        }
+    }
+    if lowercase.ends_with('s') && !lowercase.ends_with("ss") && !lowercase.ends_with("us") {
+        return value[..value.len() - 1].to_string();
+    }
+    value.to_string()
+}
+
+/// Replaces characters which are not allowed in identifiers of most languages with `_`,
+/// and prefixes names starting with a digit, e.g. `2fa-code` becomes `_2fa_code`.
+pub fn sanitize_identifier(value: &str) -> String {
+    let sanitized: String = value
+        .chars()
+        .map(|character| match character.is_alphanumeric() {
+            true => character,
+            false => '_',
+        })
+        .collect();
+
+    match sanitized.chars().next() {
+        None => String::from("_"),
+        Some(first) if first.is_numeric() => format!("_{sanitized}"),
+        Some(_) => sanitized,
+    }
+}
+
+/// Singular and plural forms which don't follow the suffix rules.
+const IRREGULAR: [(&str, &str); 7] = [
+    ("person", "people"),
+    ("child", "children"),
+    ("mouse", "mice"),
+    ("index", "indices"),
+    ("movie", "movies"),
+    ("cookie", "cookies"),
+    ("cache", "caches"),
+];
+
+fn join(value: &str, separator: &str) -> String {
+    words(value).join(separator)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut characters = word.chars();
+    match characters.next() {
+        Some(first) => {
+            first.to_uppercase().collect::<String>() + characters.as_str().to_lowercase().as_str()
+        }
+        None => String::new(),
+    }
+}
+
+/// Writes a replacement in upper case if the text it replaces or follows is upper case,
+/// and capitalized if that text is capitalized.
+fn match_case(original: &str, replacement: &str) -> String {
+    if original.chars().any(char::is_alphabetic)
+        && original.chars().all(|character| !character.is_lowercase())
+    {
+        return replacement.to_uppercase();
+    }
+    match original.chars().next() {
+        Some(first) if first.is_uppercase() => capitalize(replacement),
+        _ => replacement.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_words() {
+        assert_eq!(words("HTTPServer2"), ["HTTP", "Server2"]);
+        assert_eq!(words("x-next"), ["x", "next"]);
+        assert_eq!(words("petId"), ["pet", "Id"]);
+        assert_eq!(words("/pets/{petId}"), ["pets", "pet", "Id"]);
+        assert_eq!(words("2fa_code"), ["2fa", "code"]);
+        assert_eq!(words("v2Api"), ["v2", "Api"]);
+        assert!(words("--").is_empty());
+    }
+
+    #[test]
+    fn converts_case() {
+        assert_eq!(pascal_case("HTTPServer2"), "HttpServer2");
+        assert_eq!(camel_case("HTTPServer2"), "httpServer2");
+        assert_eq!(snake_case("HTTPServer2"), "http_server2");
+        assert_eq!(screaming_snake_case("HTTPServer2"), "HTTP_SERVER2");
+        assert_eq!(kebab_case("HTTPServer2"), "http-server2");
+
+        assert_eq!(pascal_case("x-next"), "XNext");
+        assert_eq!(camel_case("x-next"), "xNext");
+        assert_eq!(snake_case("x-next"), "x_next");
+        assert_eq!(screaming_snake_case("x-next"), "X_NEXT");
+
+        assert_eq!(pascal_case("petId"), "PetId");
+        assert_eq!(camel_case("petId"), "petId");
+        assert_eq!(snake_case("petId"), "pet_id");
+        assert_eq!(kebab_case("petId"), "pet-id");
+
+        assert_eq!(pascal_case("200"), "200");
+        assert_eq!(snake_case("2fa-code"), "2fa_code");
+        assert_eq!(camel_case("2fa-code"), "2faCode");
+    }
+
+    #[test]
+    fn pluralizes() {
+        assert_eq!(pluralize("Pet"), "Pets");
+        assert_eq!(pluralize("PetCategory"), "PetCategories");
+        assert_eq!(pluralize("day"), "days");
+        assert_eq!(pluralize("box"), "boxes");
+        assert_eq!(pluralize("Address"), "Addresses");
+        assert_eq!(pluralize("Person"), "People");
+        assert_eq!(pluralize("STATUS"), "STATUSES");
+        assert_eq!(pluralize("Human"), "Humans");
+        assert_eq!(pluralize("Movie"), "Movies");
+        assert_eq!(pluralize("Response"), "Responses");
+    }
+
+    #[test]
+    fn singularizes() {
+        assert_eq!(singularize("Pets"), "Pet");
+        assert_eq!(singularize("PetCategories"), "PetCategory");
+        assert_eq!(singularize("boxes"), "box");
+        assert_eq!(singularize("Addresses"), "Address");
+        assert_eq!(singularize("People"), "Person");
+        assert_eq!(singularize("Address"), "Address");
+        assert_eq!(singularize("Status"), "Status");
+        assert_eq!(singularize("Statuses"), "Status");
+        assert_eq!(singularize("Buses"), "Bus");
+        assert_eq!(singularize("Classes"), "Class");
+        assert_eq!(singularize("Matches"), "Match");
+        assert_eq!(singularize("Wishes"), "Wish");
+        assert_eq!(singularize("Buzzes"), "Buzz");
+        assert_eq!(singularize("Responses"), "Response");
+        assert_eq!(singularize("Purchases"), "Purchase");
+        assert_eq!(singularize("Databases"), "Database");
+        assert_eq!(singularize("Houses"), "House");
+        assert_eq!(singularize("Sizes"), "Size");
+        assert_eq!(singularize("Caches"), "Cache");
+        assert_eq!(singularize("Movies"), "Movie");
+        assert_eq!(singularize("Pies"), "Pie");
+        assert_eq!(singularize("PetMovies"), "PetMovie");
+    }
+
+    #[test]
+    fn sanitizes_identifiers() {
+        assert_eq!(sanitize_identifier("petId"), "petId");
+        assert_eq!(sanitize_identifier("x-next"), "x_next");
+        assert_eq!(sanitize_identifier("2fa"), "_2fa");
+        assert_eq!(sanitize_identifier("$ref"), "_ref");
+        assert_eq!(sanitize_identifier(""), "_");
+    }
+}
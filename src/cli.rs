@@ -1,4 +1,7 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::commands::DumpFormat;
 
 #[derive(Parser)]
 #[clap(name = "OpenAPI Manager", version)]
@@ -9,7 +12,11 @@ pub struct Arguments {
 
 #[derive(Subcommand)]
 pub enum Command {
-    Init,
+    Init {
+        /// Copy the built-in language scripts to `.openapi/languages` for customization without asking.
+        #[arg(long)]
+        eject: bool,
+    },
     Push,
     Create {
         name: String,
@@ -17,12 +24,85 @@ pub enum Command {
     Pull {
         name: String,
     },
+    /// Inspect flavours.
+    Flavour {
+        #[clap(subcommand)]
+        command: FlavourCommand,
+    },
     Run {
         #[arg(short, long)]
         schema: Option<String>,
         #[arg(short, long)]
         flavour: Option<String>,
+        /// Set an option of the flavour, e.g. `-o use_validator=true`. May be repeated.
+        #[arg(short = 'o', long = "option", value_name = "KEY=VALUE")]
+        options: Vec<String>,
+        /// Print a diff of the files which would change, without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+        /// Fail if any generated file is out of date, without writing anything.
+        #[arg(long, conflicts_with = "dry_run")]
+        check: bool,
+        /// Write a JSON report of the out of date files, used with --check.
+        #[arg(long, requires = "check")]
+        report: Option<PathBuf>,
+        /// Regenerate whenever the schema, a file it references or the flavour changes.
+        #[arg(long, conflicts_with = "check")]
+        watch: bool,
+        /// Print the context each template receives instead of rendering.
+        #[arg(long, conflicts_with_all = ["dry_run", "check", "watch"])]
+        dump_context: bool,
+        /// Only dump the context of the template with this input, used with --dump-context.
+        #[arg(long, requires = "dump_context")]
+        template: Option<String>,
+        /// Only dump the context of this entity of iterated templates, used with --dump-context.
+        #[arg(long, requires = "dump_context")]
+        entity: Option<String>,
+        /// The format of the dump.
+        #[arg(long, value_enum, default_value_t = DumpFormat::Json, requires = "dump_context")]
+        format: DumpFormat,
+        /// Write the dump to a file instead of printing it.
+        #[arg(long, requires = "dump_context")]
+        output: Option<PathBuf>,
+        /// Delete files the flavour no longer produces even if they were modified since generation.
+        #[arg(long)]
+        force: bool,
+        /// Render every file again, even those whose inputs did not change since the last run.
+        #[arg(long)]
+        no_cache: bool,
+    },
+    /// Render a single template against the schema, without setting up a flavour.
+    Render {
+        /// The template file, or the template itself, e.g. `'{{ info.title }}'`. Paths of missing files,
+        /// such as `model.tera`, are an error.
+        template: String,
+        #[arg(short, long)]
+        schema: Option<String>,
+        /// Render the template once per item of this dotted path, e.g. `components.schemas`.
+        #[arg(long)]
+        each: Option<String>,
+        /// Write to this path instead of printing, e.g. `out/{entity}.rs`.
+        #[arg(long)]
+        output: Option<String>,
+        /// The language whose filters the template uses.
+        #[arg(long, default_value = "rust")]
+        language: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum FlavourCommand {
+    /// Print a flavour with its `extends` chain resolved, including where each template is loaded from.
+    Show { name: String },
+    /// Render the fixtures under `tests/` of a flavour and compare the output to their snapshots.
+    Test {
+        name: String,
+        /// Replace the snapshots with the rendered output.
+        #[arg(long)]
+        bless: bool,
     },
+    /// Check the templates of a flavour for unknown variables, filters and placeholders without rendering them.
+    Check { name: String },
 }
 
 impl Command {
@@ -0,0 +1,273 @@
+use anyhow::anyhow;
+use colored::Colorize;
+use std::{
+    fs::{copy, create_dir_all, read, read_dir, read_to_string, remove_dir_all},
+    path::Path,
+};
+
+use crate::{
+    config::AppConfig,
+    diff::print_diff,
+    flavour::{directory, files, get_flavour_config, partials, read_flavour, Flavour},
+    ir::Ir,
+    lint::lint,
+    options,
+    plan::plan,
+    renderer::Renderer,
+    schema::OpenAPI,
+};
+
+/// Prints a flavour with its `extends` chain resolved.
+pub fn show(name: String) -> anyhow::Result<()> {
+    let flavour = get_flavour_config(name)?;
+
+    println!();
+    println!("Flavour {}", flavour.name.blue());
+    if flavour.chain.len() > 1 {
+        println!("Extends {}", flavour.chain[1..].join(" -> "));
+    }
+    if let Some(version) = &flavour.version {
+        println!("Version {version}");
+    }
+    println!("Language {}", flavour.language.bold());
+    println!();
+
+    for template in &flavour.templates {
+        println!("{} -> {}", template.input.bold(), template.output);
+        println!("  {} {}", "source".dimmed(), template.source.display());
+        if let Some(iteration) = &template.iteration {
+            println!("  {} {iteration}", "iteration".dimmed());
+        }
+        println!("  {} {}", "mode".dimmed(), template.mode);
+        if let Some(when) = &template.when {
+            println!("  {} {when}", "when".dimmed());
+        }
+    }
+    println!();
+
+    if !flavour.assets.is_empty() {
+        println!("Assets");
+        for asset in &flavour.assets {
+            println!("  {} -> {}", asset.input.bold(), asset.output);
+            println!("    {} {}", "source".dimmed(), asset.source.display());
+            if let Some(permissions) = asset.permissions {
+                println!("    {} {permissions:o}", "permissions".dimmed());
+            }
+            println!("    {} {}", "mode".dimmed(), asset.mode);
+        }
+        println!();
+    }
+
+    if !flavour.fragments.is_empty() {
+        println!("Fragments");
+        for fragment in &flavour.fragments {
+            println!("  {}", fragment.output.bold());
+            for line in toml::to_string(&fragment.value)?.lines() {
+                println!("    {}", line.dimmed());
+            }
+        }
+        println!();
+    }
+
+    let partials = partials(&flavour)?;
+    if !partials.is_empty() {
+        println!("Partials");
+        for (name, path) in partials {
+            println!("  {} {}", name.bold(), path.display().to_string().dimmed());
+        }
+        println!();
+    }
+
+    if !flavour.options.is_empty() {
+        println!("Options");
+        for (name, option) in &flavour.options {
+            let default = match &option.default {
+                Some(default) => format!(" = {default}"),
+                None => String::from(" (required)"),
+            };
+            println!("  {}: {}{default}", name.bold(), option.r#type);
+            if let Some(values) = &option.values {
+                println!("    {} {}", "one of".dimmed(), values.join(", "));
+            }
+            if let Some(description) = &option.description {
+                println!("    {}", description.dimmed());
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Renders the fixtures of a flavour and compares the output to their snapshots.
+///
+/// Every directory `tests/<case>` of a flavour holds an `openapi.yaml`, optionally an `options.toml`
+/// with values of the options of the flavour, and the expected output under `expected/`.
+/// Fixtures are written to a temporary directory like `oam run` writes to a project, which starts
+/// out with the files under `project/` of the fixture if any, so they are merged into or appended to.
+/// The snapshot is everything in that directory afterwards.
+/// `bless` replaces the snapshots with the rendered output instead.
+pub fn test(name: String, bless: bool) -> anyhow::Result<()> {
+    let flavour = get_flavour_config(name.clone())?;
+    let tests = directory(&name).join("tests");
+    let mut cases: Vec<_> = read_dir(&tests)
+        .map_err(|error| anyhow!("Could not read fixtures at {}: {error}", tests.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    cases.sort();
+
+    println!();
+    let mut failed = 0;
+    for case in &cases {
+        let label = case.file_name().unwrap_or_default().to_string_lossy();
+        println!("Fixture {}", label.bold());
+        match test_case(&flavour, case, bless) {
+            Ok(true) if bless => println!("{}", "blessed".green()),
+            Ok(true) => println!("{}", "ok".green()),
+            Ok(false) => {
+                failed += 1;
+                println!("{}", "FAILED".red());
+            }
+            Err(error) => {
+                failed += 1;
+                println!("{error:#}");
+                println!("{}", "FAILED".red());
+            }
+        }
+        println!();
+    }
+
+    match failed {
+        0 => {
+            println!("{} fixtures of flavour {} passed", cases.len(), name.blue());
+            println!();
+            Ok(())
+        }
+        _ => Err(anyhow!(
+            "{failed} of {} fixtures of flavour {name} failed, run oam flavour test {name} --bless to accept the new output",
+            cases.len()
+        )),
+    }
+}
+
+/// Checks the templates and configuration of a flavour without rendering it, see [lint].
+pub fn check(name: String) -> anyhow::Result<()> {
+    let flavour = read_flavour(name.clone())?;
+    let problems = lint(&flavour)?;
+
+    println!();
+    for problem in &problems {
+        println!("{}", problem.location.bold());
+        println!("  {}", problem.message.red());
+    }
+    if !problems.is_empty() {
+        println!();
+        return Err(anyhow!(
+            "Found {} problems in flavour {name}",
+            problems.len()
+        ));
+    }
+
+    println!(
+        "Checked {} templates of flavour {}, no problems found",
+        flavour.templates.len(),
+        name.blue()
+    );
+    println!();
+    Ok(())
+}
+
+/// Renders a fixture and prints how its output differs from the snapshot.
+/// Returns whether they are the same.
+fn test_case(flavour: &Flavour, case: &Path, bless: bool) -> anyhow::Result<bool> {
+    let label = case.file_name().unwrap_or_default().to_string_lossy();
+    let project = std::env::temp_dir().join(format!(
+        "oam-{}-{label}-{}",
+        flavour.name,
+        std::process::id()
+    ));
+    if project.exists() {
+        remove_dir_all(&project)?;
+    }
+    let result = render_case(flavour, case, &project).and_then(|()| compare(case, &project, bless));
+    remove_dir_all(&project)?;
+    result
+}
+
+/// Renders a fixture into the directory `project`, with the real writer.
+fn render_case(flavour: &Flavour, case: &Path, project: &Path) -> anyhow::Result<()> {
+    let spec = case.join("openapi.yaml");
+    let document = OpenAPI::from(&spec.to_string_lossy())?;
+    let ir = Ir::build(&document)?;
+
+    let mut config = AppConfig::new(Some(spec.display().to_string()), Some(flavour.name.clone()));
+    let options = case.join("options.toml");
+    if options.exists() {
+        config.options.insert(
+            flavour.name.clone(),
+            toml::from_str(&read_to_string(&options)?)?,
+        );
+    }
+    let options = options::resolve(flavour, &config)?;
+    let mut rendered = Renderer::new(flavour, options)?
+        .with_spec(&spec)
+        .render(flavour, &document, &ir, None)?;
+
+    create_dir_all(project)?;
+    copy_tree(&case.join("project"), project)?;
+    for file in &mut rendered {
+        file.path = project.join(&file.path);
+    }
+    for change in plan(&rendered, &Default::default(), false)? {
+        change.apply()?;
+    }
+    Ok(())
+}
+
+/// Compares the files of `project` to the snapshot of a fixture, printing how they differ,
+/// or replaces the snapshot with them if `bless` is set. Returns whether they are the same.
+fn compare(case: &Path, project: &Path, bless: bool) -> anyhow::Result<bool> {
+    let snapshot = case.join("expected");
+    if bless {
+        if snapshot.exists() {
+            remove_dir_all(&snapshot)?;
+        }
+        copy_tree(project, &snapshot)?;
+        return Ok(true);
+    }
+
+    // Both are keyed by their path relative to the directory, with `/` as separator.
+    let mut expected = files(&snapshot)?;
+    let mut same = true;
+    for (path, file) in files(project)? {
+        let before = match expected.remove(&path) {
+            Some(path) => Some(read(path)?),
+            None => None,
+        };
+        let after = read(&file)?;
+        if before.as_ref() != Some(&after) {
+            same = false;
+            print_diff(Path::new(&path), before.as_deref(), Some(&after));
+        }
+    }
+    // Expected files which were not rendered.
+    for (path, snapshot) in expected {
+        same = false;
+        print_diff(Path::new(&path), Some(&read(snapshot)?), None);
+    }
+
+    Ok(same)
+}
+
+/// Copies every file under `from` to the same path under `to`. A missing directory has no files.
+fn copy_tree(from: &Path, to: &Path) -> anyhow::Result<()> {
+    for (relative, source) in files(from)? {
+        let target = to.join(relative);
+        if let Some(parent) = target.parent() {
+            create_dir_all(parent)?;
+        }
+        copy(source, target)?;
+    }
+    Ok(())
+}
@@ -1,8 +1,13 @@
-use std::fs::{create_dir, File};
+use std::{
+    fs::{create_dir, File},
+    io::{stdin, stdout, IsTerminal, Write},
+};
 
 use colored::Colorize;
 
-pub fn init() -> anyhow::Result<()> {
+use crate::language;
+
+pub fn init(eject: bool) -> anyhow::Result<()> {
     let mut created_entities: u8 = 0;
     println!();
     if create_dir(".openapi").is_ok() {
@@ -13,14 +18,25 @@ pub fn init() -> anyhow::Result<()> {
         created_entities += 1;
         println!("Created directory {}", ".openapi/flavours".bold());
     }
-    if create_dir(".openapi/languages").is_ok() {
-        created_entities += 1;
-        println!("Created directory {}", ".openapi/languages".bold());
-    }
     if File::create_new(".openapi/config.toml").is_ok() {
         created_entities += 1;
         println!("Created config file {}", ".openapi/config.toml".bold());
     }
+    if eject || offer_eject()? {
+        if create_dir(".openapi/languages").is_ok() {
+            created_entities += 1;
+            println!("Created directory {}", ".openapi/languages".bold());
+        }
+        // Never overwrite scripts which were ejected and customized before.
+        for (name, script) in language::BUILTIN {
+            let path = format!(".openapi/languages/{name}.rhai");
+            if let Ok(mut file) = File::create_new(&path) {
+                file.write_all(script.as_bytes())?;
+                created_entities += 1;
+                println!("Ejected language {} to {}", name.blue(), path.bold());
+            }
+        }
+    }
 
     if created_entities > 0 {
         println!();
@@ -36,3 +52,15 @@ pub fn init() -> anyhow::Result<()> {
     println!();
     Ok(())
 }
+
+/// Asks whether to eject the built-in language scripts, unless not run interactively.
+fn offer_eject() -> anyhow::Result<bool> {
+    if !stdin().is_terminal() {
+        return Ok(false);
+    }
+    print!("Eject the built-in language scripts to .openapi/languages for customization? [y/N] ");
+    stdout().flush()?;
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
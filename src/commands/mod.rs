@@ -1,9 +1,14 @@
 mod create;
+pub mod flavour;
 mod init;
 mod pull;
+mod render;
 mod run;
+mod watch;
 
 pub use create::create;
 pub use init::init;
 pub use pull::pull;
-pub use run::run;
+pub use render::render;
+pub use run::{run, DumpFormat, Mode};
+pub use watch::watch;
@@ -0,0 +1,203 @@
+use anyhow::anyhow;
+use colored::Colorize;
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, read_to_string, write},
+    io::{stdout, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::AppConfig,
+    flavour::{Flavour, Template, WriteMode},
+    ir::Ir,
+    renderer::{RenderedFile, Renderer},
+    schema::OpenAPI,
+};
+
+/// Where `oam render` sends what it renders.
+enum Destination {
+    /// Printed, one item after the other.
+    Stdout,
+    /// Written to the files of an output pattern, e.g. `out/{entity}.rs`.
+    Files(String),
+}
+
+/// Renders a single template against the schema, without a flavour.
+///
+/// `template` is the path of a template file or else the template itself, e.g. `'{{ info.title }}'`.
+/// With `each`, the template is rendered once per item like an iterated template of a flavour.
+/// The output is printed unless an `output` pattern is given, e.g. `out/{entity}.rs`.
+pub fn render(
+    template: String,
+    config: AppConfig,
+    each: Option<String>,
+    output: Option<String>,
+    language: String,
+) -> anyhow::Result<()> {
+    let destination = match output {
+        Some(pattern) => Destination::Files(pattern),
+        None => Destination::Stdout,
+    };
+    let files = rendered(&template, &config.schema, each, &destination, language)?;
+
+    for file in files {
+        if let Destination::Stdout = destination {
+            stdout().write_all(&file.contents)?;
+            continue;
+        }
+        if let Some(parent) = file.path.parent() {
+            create_dir_all(parent)?;
+        }
+        write(&file.path, file.contents)?;
+        println!("Generated {}", file.path.display().to_string().bold());
+    }
+
+    Ok(())
+}
+
+/// Renders `template` against the schema at `schema` for `destination`, without writing anything.
+fn rendered(
+    template: &str,
+    schema: &str,
+    each: Option<String>,
+    destination: &Destination,
+    language: String,
+) -> anyhow::Result<Vec<RenderedFile>> {
+    let document = OpenAPI::from(schema)?;
+    let ir = Ir::build(&document)?;
+
+    let (source, contents) = source(template)?;
+    let mut flavour = Flavour {
+        name: String::from("render"),
+        version: None,
+        language,
+        templates: Vec::new(),
+        assets: Vec::new(),
+        fragments: Vec::new(),
+        options: BTreeMap::new(),
+        chain: Vec::new(),
+    };
+    let renderer = Renderer::new(&flavour, Default::default())?
+        .with_spec(schema)
+        .with_template("render", &contents)?;
+    flavour.templates.push(Template {
+        input: String::from("render"),
+        // Files rendered for an empty output have no path, see [Renderer::render].
+        output: match destination {
+            Destination::Stdout => String::new(),
+            Destination::Files(pattern) => pattern.clone(),
+        },
+        iteration: each,
+        mode: WriteMode::Overwrite,
+        when: None,
+        source,
+    });
+
+    renderer.render(&flavour, &document, &ir, None)
+}
+
+/// Where a template comes from and its contents: the file at `template`, or else `template` itself.
+/// An argument which looks like a path, i.e. ends in `.tera` or contains `/` but no tags, must exist,
+/// so a mistyped path isn't printed back as a template.
+fn source(template: &str) -> anyhow::Result<(PathBuf, String)> {
+    if Path::new(template).is_file() {
+        return Ok((PathBuf::from(template), read_to_string(template)?));
+    }
+
+    let tags = template.contains("{{") || template.contains("{%");
+    if template.ends_with(".tera") || (template.contains('/') && !tags) {
+        return Err(anyhow!("Template file {template} not found"));
+    }
+    Ok((PathBuf::from("<inline>"), template.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"
+openapi: 3.1.0
+info: { title: Pets, version: "1" }
+paths: {}
+components:
+  schemas:
+    Cat: { type: object }
+    Dog: { type: object }
+"#;
+
+    /// The path and contents of every file rendered from `template`.
+    fn render(
+        template: &str,
+        each: Option<&str>,
+        destination: Destination,
+    ) -> Vec<(String, String)> {
+        let directory = std::env::temp_dir().join(format!("oam-render-{}", std::process::id()));
+        create_dir_all(&directory).unwrap();
+        let schema = directory.join("openapi.yaml");
+        write(&schema, DOCUMENT).unwrap();
+
+        rendered(
+            template,
+            &schema.display().to_string(),
+            each.map(str::to_string),
+            &destination,
+            String::from("rust"),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|file| {
+            (
+                file.path.display().to_string(),
+                String::from_utf8(file.contents).unwrap(),
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn renders_inline_templates() {
+        assert_eq!(
+            render("{{ info.title }}", None, Destination::Stdout),
+            [(String::new(), String::from("Pets"))]
+        );
+    }
+
+    #[test]
+    fn prints_every_item() {
+        assert_eq!(
+            render(
+                "{{ entity }}\n",
+                Some("components.schemas"),
+                Destination::Stdout
+            ),
+            [
+                (String::new(), String::from("Cat\n")),
+                (String::new(), String::from("Dog\n"))
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_files_of_the_output_pattern() {
+        assert_eq!(
+            render(
+                "struct {{ entity }};",
+                Some("components.schemas"),
+                Destination::Files(String::from("out/{entity}.rs"))
+            ),
+            [
+                (String::from("out/Cat.rs"), String::from("struct Cat;")),
+                (String::from("out/Dog.rs"), String::from("struct Dog;"))
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_missing_template_files() {
+        for template in ["modle.tera", "templates/model"] {
+            assert!(source(template).is_err(), "{template}");
+        }
+        assert!(source("// {{ info.title }}").is_ok());
+    }
+}
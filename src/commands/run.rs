@@ -1,23 +1,243 @@
+use anyhow::anyhow;
 use colored::Colorize;
+use serde::Serialize;
+use std::{fs::write, path::PathBuf};
 
-use crate::{config::AppConfig, flavour::get_flavour_config, schema::OpenAPI};
+use crate::{
+    config::AppConfig,
+    diff::{print_diff, unified_diff},
+    flavour::get_flavour_config,
+    ir::Ir,
+    manifest::Manifest,
+    options,
+    plan::{plan, Change, ChangeKind, Summary},
+    renderer::Renderer,
+    schema::OpenAPI,
+};
 
-pub fn run(config: AppConfig) -> anyhow::Result<()> {
+/// What `oam run` does with the rendered files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+    /// Write the files to disk.
+    Write,
+    /// Print a diff of every file which would change, without writing anything.
+    DryRun,
+    /// Fail if any file on disk differs from what would be generated, optionally writing a JSON report.
+    Check { report: Option<PathBuf> },
+    /// Print the context of each template instead of rendering, or write it to `output`.
+    DumpContext {
+        template: Option<String>,
+        entity: Option<String>,
+        format: DumpFormat,
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum DumpFormat {
+    Json,
+    Yaml,
+}
+
+/// The outcome of `oam run --check`.
+#[derive(Serialize)]
+struct Report<'a> {
+    schema: &'a str,
+    flavour: &'a str,
+    /// The files which differ from what would be generated.
+    files: Vec<Drift<'a>>,
+}
+
+#[derive(Serialize)]
+struct Drift<'a> {
+    path: String,
+    kind: ChangeKind,
+    diff: String,
+    warnings: &'a [String],
+}
+
+impl<'a> From<&'a Change> for Drift<'a> {
+    fn from(change: &'a Change) -> Self {
+        Drift {
+            path: change.path.display().to_string(),
+            kind: change.kind,
+            diff: unified_diff(
+                &change.path,
+                change.before.as_deref(),
+                change.after.as_deref(),
+            ),
+            warnings: &change.warnings,
+        }
+    }
+}
+
+/// Only files whose inputs changed since the last run are rendered again, unless `no_cache` is set.
+/// Files the flavour no longer produces are deleted, unless modified since generation.
+/// `force` deletes modified files as well.
+pub fn run(config: AppConfig, mode: Mode, force: bool, no_cache: bool) -> anyhow::Result<()> {
+    // A dump printed to stdout must be nothing but the dump.
+    let dump = matches!(mode, Mode::DumpContext { .. });
+    if !dump {
+        println!();
+        println!(
+            "Will generate based on OpenAPI schema {} using flavour {}...",
+            config.schema.bold(),
+            config.flavour.blue()
+        );
+        println!();
+    }
+
+    // Retrieve schema from file.
+    let schema = OpenAPI::from(&config.schema)?;
+
+    // Lower schema into the representation generators render from.
+    let ir = Ir::build(&schema)?;
+
+    // Retrieve flavour config.
+    let flavour = get_flavour_config(config.flavour.clone())?;
+
+    // Render flavour templates in memory and compare them to the files on disk.
+    let options = options::resolve(&flavour, &config)?;
+    let renderer = Renderer::new(&flavour, options)?.with_spec(&config.schema);
+    if let Mode::DumpContext {
+        template,
+        entity,
+        format,
+        output,
+    } = &mode
+    {
+        let contexts = renderer.contexts(
+            &flavour,
+            &schema,
+            &ir,
+            template.as_deref(),
+            entity.as_deref(),
+        )?;
+        if contexts.is_empty() {
+            return Err(anyhow!("No template or entity matches the filter"));
+        }
+        let dump = match format {
+            DumpFormat::Json => serde_json::to_string_pretty(&contexts)? + "\n",
+            DumpFormat::Yaml => serde_yaml::to_string(&contexts)?,
+        };
+        match output {
+            Some(path) => write(path, dump)?,
+            None => print!("{dump}"),
+        }
+        return Ok(());
+    }
+
+    let mut manifest = Manifest::load()?;
+    let generated = manifest.files(&config.flavour);
+    let cache = (!no_cache).then_some(&generated);
+    let files = renderer.render(&flavour, &schema, &ir, cache)?;
+    let changes = plan(&files, &generated, force)?;
+
+    for change in &changes {
+        let path = change.path.display().to_string();
+        match &mode {
+            Mode::Write => {
+                change.apply()?;
+                match change.kind {
+                    ChangeKind::Created | ChangeKind::Modified => {
+                        println!("Generated {}", path.bold())
+                    }
+                    ChangeKind::Deleted => println!("Deleted {}", path.bold()),
+                    ChangeKind::Unchanged | ChangeKind::Orphaned => {
+                        println!("Kept {}", path.dimmed())
+                    }
+                }
+            }
+            Mode::DryRun
+                if matches!(
+                    change.kind,
+                    ChangeKind::Created | ChangeKind::Modified | ChangeKind::Deleted
+                ) =>
+            {
+                print_diff(
+                    &change.path,
+                    change.before.as_deref(),
+                    change.after.as_deref(),
+                );
+                println!();
+            }
+            Mode::DryRun => {}
+            Mode::Check { .. } if change.is_drift() => {
+                println!("{} {}", format!("{:?}", change.kind).red(), path.bold());
+            }
+            Mode::Check { .. } | Mode::DumpContext { .. } => {}
+        }
+        for warning in &change.warnings {
+            println!("{}", warning.yellow());
+        }
+    }
     println!();
+
+    if mode == Mode::Write {
+        manifest.record(&config.flavour, &changes);
+        manifest.save()?;
+    }
+
+    if let Mode::Check { report } = &mode {
+        return check(&config, &changes, report.as_ref());
+    }
+
+    let summary = Summary::of(&changes);
+    let verb = match mode {
+        Mode::Write => "",
+        _ => "would be ",
+    };
     println!(
-        "Will generate based on OpenAPI schema {} using flavour {}...",
-        config.schema.bold(),
-        config.flavour.blue()
+        "{} {verb}created, {} {verb}modified, {} {verb}deleted, {} unchanged",
+        summary.created.to_string().green(),
+        summary.modified.to_string().yellow(),
+        summary.deleted.to_string().red(),
+        summary.unchanged
     );
+    let rendered = files.iter().filter(|file| !file.cached).count();
+    if rendered < files.len() {
+        println!(
+            "{rendered} of {} files rendered, the others were up to date",
+            files.len()
+        );
+    }
+    if summary.orphaned > 0 {
+        println!(
+            "{} no longer generated but kept because modified",
+            summary.orphaned.to_string().yellow()
+        );
+    }
     println!();
 
-    // Retrieve schema from file.
-    let _schema = OpenAPI::from(&config.schema)?;
+    Ok(())
+}
 
-    // Retrieve flavour config.
-    let _flavour = get_flavour_config(config.flavour)?;
+/// Fails listing the files which drifted from what the flavour generates.
+fn check(config: &AppConfig, changes: &[Change], report: Option<&PathBuf>) -> anyhow::Result<()> {
+    let files: Vec<Drift> = changes
+        .iter()
+        .filter(|change| change.is_drift())
+        .map(Drift::from)
+        .collect();
+    let drifted = files.len();
 
-    println!("{:?} {:?}", _schema, _flavour);
+    if let Some(path) = report {
+        let report = Report {
+            schema: &config.schema,
+            flavour: &config.flavour,
+            files,
+        };
+        write(path, serde_json::to_string_pretty(&report)? + "\n")?;
+    }
 
-    Ok(())
+    match drifted {
+        0 => {
+            println!("{}", "All generated files are up to date".green());
+            println!();
+            Ok(())
+        }
+        _ => Err(anyhow!(
+            "{drifted} generated files are out of date, run oam run to regenerate them"
+        )),
+    }
 }
@@ -0,0 +1,202 @@
+use colored::Colorize;
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+use std::{
+    collections::BTreeSet,
+    fs::{canonicalize, read_to_string},
+    path::{absolute, Path, PathBuf},
+    sync::mpsc::channel,
+    time::Duration,
+};
+
+use super::{run, Mode};
+use crate::config::AppConfig;
+
+/// How long to wait for further changes before regenerating, as editors often write a file in steps.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Runs `oam run` again whenever the schema, a file it references or an input under `.openapi`
+/// changes, see `is_input`. Errors are printed and the next change is awaited.
+pub fn watch(config: AppConfig, mode: Mode, force: bool, no_cache: bool) -> anyhow::Result<()> {
+    let (sender, receiver) = channel();
+    let mut debouncer = new_debouncer(DEBOUNCE, sender)?;
+    let openapi = canonicalize(".openapi")?;
+    debouncer
+        .watcher()
+        .watch(&openapi, RecursiveMode::Recursive)?;
+
+    let mut directories = BTreeSet::new();
+    loop {
+        if let Err(error) = run(config.clone(), mode.clone(), force, no_cache) {
+            println!("{}", format!("Error: {error:#}").red());
+            println!();
+        }
+
+        // References may have changed, and editors may replace files, so their directories are watched.
+        let schemas = schema_files(Path::new(&config.schema));
+        for directory in schemas.iter().filter_map(|schema| schema.parent()) {
+            if directories.insert(directory.to_path_buf()) {
+                debouncer
+                    .watcher()
+                    .watch(directory, RecursiveMode::NonRecursive)?;
+            }
+        }
+
+        println!(
+            "{}",
+            "Watching for changes, press Ctrl-C to stop...".dimmed()
+        );
+        loop {
+            let events = match receiver.recv() {
+                Ok(Ok(events)) => events,
+                Ok(Err(error)) => {
+                    println!("{}", format!("Could not watch files: {error}").yellow());
+                    continue;
+                }
+                Err(_) => return Ok(()),
+            };
+
+            // Watchers report paths as the platform resolves them, e.g. under `/private/var` on macOS.
+            let relevant = events.iter().any(|event| {
+                let path = canonical(&event.path);
+                schemas.contains(&path) || is_input(&openapi, &path)
+            });
+            if relevant {
+                break;
+            }
+        }
+    }
+}
+
+/// Whether a file under the `.openapi` directory at `openapi` is read by `oam run`: the configuration,
+/// or a file of a flavour, partial or language script, but not the manifest or files editors
+/// write next to the ones being edited.
+fn is_input(openapi: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(openapi) else {
+        return false;
+    };
+    let name = relative
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let temporary = name.starts_with(".#")
+        || name.ends_with('~')
+        || name.ends_with(".swp")
+        || name.ends_with(".swx")
+        || name == "4913";
+    let input = relative == Path::new("config.toml")
+        || ["flavours", "partials", "languages"]
+            .iter()
+            .any(|directory| relative.starts_with(directory));
+    input && !temporary
+}
+
+/// The canonical form of a path, which for a file that no longer or not yet exists is that of its
+/// directory joined with its name.
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(path) = canonicalize(path) {
+        return path;
+    }
+    match (path.parent().map(canonicalize), path.file_name()) {
+        (Some(Ok(directory)), Some(name)) => directory.join(name),
+        _ => absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+/// The canonical paths of the schema and the files it references through `$ref`, transitively.
+/// Files which cannot be read are still included, so they are picked up once they are created.
+fn schema_files(schema: &Path) -> BTreeSet<PathBuf> {
+    let mut files = BTreeSet::new();
+    let mut pending = vec![schema.to_path_buf()];
+
+    while let Some(file) = pending.pop() {
+        let file = canonical(&file);
+        if !files.insert(file.clone()) {
+            continue;
+        }
+
+        let Some(document) = read_to_string(&file)
+            .ok()
+            .and_then(|contents| serde_yaml::from_str(&contents).ok())
+        else {
+            continue;
+        };
+        let directory = file.parent().unwrap_or(Path::new(""));
+        let mut references = Vec::new();
+        collect_references(&document, &mut references);
+        pending.extend(
+            references
+                .into_iter()
+                .map(|reference| directory.join(reference)),
+        );
+    }
+
+    files
+}
+
+/// Collects the files of external references such as `common.yaml#/components/schemas/Error`.
+fn collect_references(value: &serde_yaml::Value, references: &mut Vec<String>) {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => {
+            for (key, value) in mapping {
+                let reference = match (key.as_str(), value.as_str()) {
+                    (Some("$ref"), Some(reference)) => reference,
+                    _ => {
+                        collect_references(value, references);
+                        continue;
+                    }
+                };
+                let file = reference.split('#').next().unwrap_or_default();
+                if !file.is_empty() && !file.contains("://") {
+                    references.push(file.to_string());
+                }
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                collect_references(item, references);
+            }
+        }
+        serde_yaml::Value::Tagged(tagged) => collect_references(&tagged.value, references),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watches_inputs_only() {
+        let openapi = Path::new("/project/.openapi");
+        for input in [
+            "config.toml",
+            "flavours/axum/config.toml",
+            "flavours/axum/model.tera",
+            "flavours/axum/static/.gitignore",
+            "partials/header.tera",
+            "languages/rust.rhai",
+        ] {
+            assert!(is_input(openapi, &openapi.join(input)), "{input}");
+        }
+        for other in [
+            "manifest.toml",
+            "notes.md",
+            "flavours/axum/.model.tera.swp",
+            "flavours/axum/model.tera~",
+            "flavours/axum/.#model.tera",
+            "flavours/axum/4913",
+        ] {
+            assert!(!is_input(openapi, &openapi.join(other)), "{other}");
+        }
+        assert!(!is_input(openapi, Path::new("/project/openapi.yaml")));
+    }
+
+    #[test]
+    fn canonicalizes_missing_files() {
+        let directory = canonicalize(std::env::temp_dir()).unwrap();
+        assert_eq!(
+            canonical(&std::env::temp_dir().join("oam-missing.yaml")),
+            directory.join("oam-missing.yaml")
+        );
+    }
+}
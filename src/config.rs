@@ -1,9 +1,37 @@
+use anyhow::anyhow;
 use serde::Deserialize;
+use std::{collections::BTreeMap, fs::read_to_string, path::Path};
 
-#[derive(Deserialize)]
+const PATH: &str = ".openapi/config.toml";
+
+/// The configuration of a project, read from `.openapi/config.toml` and overridden from the command line.
+///
+/// ```toml
+/// schema = "api/openapi.yaml"
+/// flavour = "axum"
+///
+/// [options.axum]
+/// use_validator = true
+/// ```
+#[derive(Deserialize, Clone)]
 pub struct AppConfig {
     pub schema: String,
     pub flavour: String,
+    /// Values of flavour options, keyed by flavour.
+    #[serde(default)]
+    pub options: BTreeMap<String, toml::Table>,
+    /// Values of flavour options set with `-o key=value`, which take precedence.
+    #[serde(skip)]
+    pub overrides: Vec<(String, String)>,
+}
+
+/// The fields of `.openapi/config.toml`, all of which may be omitted.
+#[derive(Deserialize, Default)]
+struct ProjectConfig {
+    schema: Option<String>,
+    flavour: Option<String>,
+    #[serde(default)]
+    options: BTreeMap<String, toml::Table>,
 }
 
 impl AppConfig {
@@ -11,8 +39,33 @@ impl AppConfig {
         AppConfig {
             schema: schema.unwrap_or(String::from("openapi.yaml")),
             flavour: flavour.unwrap_or(String::from("default")),
+            options: BTreeMap::new(),
+            overrides: Vec::new(),
         }
     }
 
-    pub fn load() {}
+    /// Reads the project configuration, if any, with the given values taking precedence.
+    pub fn load(
+        schema: Option<String>,
+        flavour: Option<String>,
+        overrides: Vec<String>,
+    ) -> anyhow::Result<Self> {
+        let project = match Path::new(PATH).exists() {
+            true => toml::from_str(&read_to_string(PATH)?)
+                .map_err(|error| anyhow!("Invalid project configuration {PATH}: {error}"))?,
+            false => ProjectConfig::default(),
+        };
+
+        let mut config = AppConfig::new(schema.or(project.schema), flavour.or(project.flavour));
+        config.options = project.options;
+        config.overrides = overrides
+            .iter()
+            .map(|option| match option.split_once('=') {
+                Some((key, value)) => Ok((key.trim().to_string(), value.to_string())),
+                None => Err(anyhow!("Option {option} must be given as key=value")),
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(config)
+    }
 }
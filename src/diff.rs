@@ -0,0 +1,59 @@
+use colored::Colorize;
+use similar::{ChangeTag, TextDiff};
+use std::{path::Path, str::from_utf8};
+
+/// A unified diff between two versions of a file. A missing version is shown as `/dev/null`.
+/// Versions which are not UTF-8 text are only said to differ, as `git diff` does.
+pub fn unified_diff(path: &Path, before: Option<&[u8]>, after: Option<&[u8]>) -> String {
+    let (old, new) = headers(path, before, after);
+    let Some((before, after)) = text(before, after) else {
+        return format!("Binary files {old} and {new} differ\n");
+    };
+    TextDiff::from_lines(before, after)
+        .unified_diff()
+        .context_radius(3)
+        .header(&old, &new)
+        .to_string()
+}
+
+/// Prints a colored unified diff between two versions of a file.
+pub fn print_diff(path: &Path, before: Option<&[u8]>, after: Option<&[u8]>) {
+    let (old, new) = headers(path, before, after);
+    let Some((before, after)) = text(before, after) else {
+        println!("{}", format!("Binary files {old} and {new} differ").bold());
+        return;
+    };
+    let diff = TextDiff::from_lines(before, after);
+
+    println!("{}", format!("--- {old}").bold());
+    println!("{}", format!("+++ {new}").bold());
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        println!("{}", hunk.header().to_string().cyan());
+        for change in hunk.iter_changes() {
+            let line = change.value().trim_end_matches(['\r', '\n']);
+            match change.tag() {
+                ChangeTag::Delete => println!("{}", format!("-{line}").red()),
+                ChangeTag::Insert => println!("{}", format!("+{line}").green()),
+                ChangeTag::Equal => println!(" {line}"),
+            }
+            if change.missing_newline() {
+                println!("{}", "\\ No newline at end of file".dimmed());
+            }
+        }
+    }
+}
+
+/// Both versions as text, a missing one being empty, or `None` if either is not UTF-8.
+fn text<'a>(before: Option<&'a [u8]>, after: Option<&'a [u8]>) -> Option<(&'a str, &'a str)> {
+    let before = from_utf8(before.unwrap_or_default()).ok()?;
+    let after = from_utf8(after.unwrap_or_default()).ok()?;
+    Some((before, after))
+}
+
+fn headers(path: &Path, before: Option<&[u8]>, after: Option<&[u8]>) -> (String, String) {
+    let header = |prefix: &str, contents: Option<&[u8]>| match contents {
+        Some(_) => format!("{prefix}/{}", path.display()),
+        None => String::from("/dev/null"),
+    };
+    (header("a", before), header("b", after))
+}
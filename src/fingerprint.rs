@@ -0,0 +1,323 @@
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeSet, fs::read_to_string};
+use tera::{
+    ast::{Expr, ExprVal, MacroDefinition, Node},
+    Tera, Value,
+};
+
+/// Identifies everything a rendered file is made of: the binary, the language script, the
+/// configuration and sources of its templates and the parts of the context they read.
+/// A file whose fingerprint did not change need not be rendered again.
+#[derive(Clone)]
+pub struct Fingerprint(Sha256);
+
+impl Fingerprint {
+    pub fn new() -> Self {
+        let mut fingerprint = Fingerprint(Sha256::new());
+        fingerprint.add(env!("CARGO_PKG_VERSION"));
+        fingerprint
+    }
+
+    pub fn add(&mut self, part: &str) {
+        self.add_bytes(part.as_bytes());
+    }
+
+    pub fn add_bytes(&mut self, part: &[u8]) {
+        // Prefixing the length keeps `ab` + `c` apart from `a` + `bc`.
+        self.0.update(part.len().to_le_bytes());
+        self.0.update(part);
+    }
+
+    pub fn add_value(&mut self, value: Option<&Value>) {
+        self.add(&value.map(Value::to_string).unwrap_or_default());
+    }
+
+    pub fn finish(self) -> String {
+        format!("{:x}", self.0.finalize())
+    }
+}
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Fingerprint::new()
+    }
+}
+
+/// The variables a template reads and the templates it is made of, following includes,
+/// macro imports and parents.
+#[derive(Default, Debug)]
+pub struct Inputs {
+    /// Dotted paths of the variables read from the context, e.g. `item.properties` or `ir.types`.
+    /// Variables the templates declare themselves, with `set`, `for` or as macro arguments, are
+    /// left out where they are in scope.
+    pub variables: BTreeSet<String>,
+    pub templates: BTreeSet<String>,
+    /// The filters and functions called.
+    pub filters: BTreeSet<String>,
+    pub functions: BTreeSet<String>,
+    /// The variables declared in the scopes being walked, innermost last.
+    /// A macro starts over with scopes of its own, as it only sees its arguments.
+    scopes: Vec<BTreeSet<String>>,
+}
+
+impl Inputs {
+    pub fn of(tera: &Tera, name: &str) -> Self {
+        let mut inputs = Inputs {
+            scopes: vec![BTreeSet::new()],
+            ..Default::default()
+        };
+        inputs.template(tera, name);
+        inputs
+    }
+
+    /// Adds the sources of the templates to a fingerprint.
+    pub fn add_sources(&self, tera: &Tera, fingerprint: &mut Fingerprint) {
+        for name in &self.templates {
+            let path = tera
+                .get_template(name)
+                .ok()
+                .and_then(|template| template.path.as_ref());
+            fingerprint.add(name);
+            fingerprint.add(
+                &path
+                    .and_then(|path| read_to_string(path).ok())
+                    .unwrap_or_default(),
+            );
+        }
+    }
+
+    fn template(&mut self, tera: &Tera, name: &str) {
+        if !self.templates.insert(name.to_string()) {
+            return;
+        }
+        let Ok(template) = tera.get_template(name) else {
+            return;
+        };
+
+        self.nodes(tera, &template.ast);
+        for definition in template.macros.values() {
+            self.definition(tera, definition);
+        }
+        for (file, _) in &template.imported_macro_files {
+            self.template(tera, file);
+        }
+        for parent in &template.parents {
+            self.template(tera, parent);
+        }
+    }
+
+    fn nodes(&mut self, tera: &Tera, nodes: &[Node]) {
+        for node in nodes {
+            match node {
+                Node::VariableBlock(_, expr) => self.expr(expr),
+                Node::MacroDefinition(_, definition, _) => self.definition(tera, definition),
+                Node::Extends(_, name) | Node::ImportMacro(_, name, _) => self.template(tera, name),
+                Node::Include(_, names, _) => {
+                    names.iter().for_each(|name| self.template(tera, name))
+                }
+                Node::Set(_, set) => {
+                    self.expr(&set.value);
+                    let scope = match set.global {
+                        true => self.scopes.first_mut(),
+                        false => self.scopes.last_mut(),
+                    };
+                    scope.into_iter().for_each(|scope| {
+                        scope.insert(set.key.clone());
+                    });
+                }
+                Node::FilterSection(_, section, _) => {
+                    self.filters.insert(section.filter.name.clone());
+                    section
+                        .filter
+                        .args
+                        .values()
+                        .for_each(|expr| self.expr(expr));
+                    self.nodes(tera, &section.body);
+                }
+                Node::Block(_, block, _) => self.nodes(tera, &block.body),
+                Node::Forloop(_, forloop, _) => {
+                    self.expr(&forloop.container);
+                    let mut scope = BTreeSet::from([forloop.value.clone()]);
+                    scope.extend(forloop.key.clone());
+                    self.scopes.push(scope);
+                    self.nodes(tera, &forloop.body);
+                    self.scopes.pop();
+                    if let Some(body) = &forloop.empty_body {
+                        self.nodes(tera, body);
+                    }
+                }
+                Node::If(condition, _) => {
+                    for (_, expr, body) in &condition.conditions {
+                        self.expr(expr);
+                        self.nodes(tera, body);
+                    }
+                    if let Some((_, body)) = &condition.otherwise {
+                        self.nodes(tera, body);
+                    }
+                }
+                Node::Super
+                | Node::Text(_)
+                | Node::Raw(..)
+                | Node::Break(_)
+                | Node::Continue(_)
+                | Node::Comment(..) => {}
+            }
+        }
+    }
+
+    fn definition(&mut self, tera: &Tera, definition: &MacroDefinition) {
+        definition
+            .args
+            .values()
+            .flatten()
+            .for_each(|expr| self.expr(expr));
+        let arguments = definition.args.keys().cloned().collect();
+        let outer = std::mem::replace(&mut self.scopes, vec![arguments]);
+        self.nodes(tera, &definition.body);
+        self.scopes = outer;
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        self.value(&expr.val);
+        for filter in &expr.filters {
+            self.filters.insert(filter.name.clone());
+            filter.args.values().for_each(|expr| self.expr(expr));
+        }
+    }
+
+    fn value(&mut self, value: &ExprVal) {
+        match value {
+            ExprVal::Ident(ident) => self.ident(ident),
+            ExprVal::Math(math) => {
+                self.expr(&math.lhs);
+                self.expr(&math.rhs);
+            }
+            ExprVal::Logic(logic) => {
+                self.expr(&logic.lhs);
+                self.expr(&logic.rhs);
+            }
+            ExprVal::In(contains) => {
+                self.expr(&contains.lhs);
+                self.expr(&contains.rhs);
+            }
+            ExprVal::Test(test) => {
+                self.ident(&test.ident);
+                test.args.iter().for_each(|expr| self.expr(expr));
+            }
+            ExprVal::MacroCall(call) => call.args.values().for_each(|expr| self.expr(expr)),
+            ExprVal::FunctionCall(call) => {
+                self.functions.insert(call.name.clone());
+                call.args.values().for_each(|expr| self.expr(expr));
+            }
+            ExprVal::Array(items) => items.iter().for_each(|expr| self.expr(expr)),
+            ExprVal::StringConcat(concat) => {
+                concat.values.iter().for_each(|value| self.value(value))
+            }
+            ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => {}
+        }
+    }
+
+    /// Adds the paths of an identifier such as `ir.types[item.name].properties`,
+    /// i.e. `ir.types` and `item.name`.
+    fn ident(&mut self, ident: &str) {
+        for part in ident.split(['[', ']']) {
+            let literal = part.starts_with(['.', '"', '\'', '`'])
+                || part.starts_with(|character: char| character.is_ascii_digit());
+            let root = part.split('.').next().unwrap_or_default();
+            let local = self.scopes.iter().any(|scope| scope.contains(root));
+            if !part.is_empty() && !literal && !local {
+                self.variables.insert(part.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(templates: &[(&str, &str)]) -> Inputs {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(templates.to_vec()).unwrap();
+        Inputs::of(&tera, templates[0].0)
+    }
+
+    fn set(values: &[&str]) -> BTreeSet<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn collects_variables() {
+        let inputs = inputs(&[(
+            "model",
+            "{{ info.title }}{% if options.serde and item.kind == 'object' %}{{ ir.types[item.name].properties | length }}{% endif %}",
+        )]);
+        assert_eq!(
+            inputs.variables,
+            set(&[
+                "info.title",
+                "ir.types",
+                "item.kind",
+                "item.name",
+                "options.serde"
+            ])
+        );
+        assert_eq!(inputs.filters, set(&["length"]));
+    }
+
+    #[test]
+    fn skips_literal_subscripts() {
+        let inputs = inputs(&[(
+            "paths",
+            r#"{{ paths["/pets"].get }}{{ ir.operations[0].name }}{{ info['title'] }}"#,
+        )]);
+        assert_eq!(inputs.variables, set(&["info", "ir.operations", "paths"]));
+    }
+
+    #[test]
+    fn leaves_out_locals() {
+        let inputs = inputs(&[(
+            "model",
+            "{% set title = info.title %}{% for name, schema in components.schemas %}{{ file(path=name) }}{{ schema.type }}{% endfor %}{{ title }}",
+        )]);
+        assert_eq!(inputs.functions, set(&["file"]));
+        assert_eq!(inputs.variables, set(&["components.schemas", "info.title"]));
+    }
+
+    #[test]
+    fn follows_includes_imports_and_parents() {
+        let inputs = inputs(&[
+            (
+                "model",
+                r#"{% extends "base" %}{% import "macros" as macros %}{% block body %}{% include "header" %}{{ macros::field(field=item) }}{% endblock %}"#,
+            ),
+            ("base", "{% block body %}{% endblock %}{{ info.version }}"),
+            ("header", "{{ info.title }}"),
+            (
+                "macros",
+                "{% macro field(field, rename=false) %}{{ field.name | snake_case }}{% endmacro %}",
+            ),
+        ]);
+        assert_eq!(
+            inputs.templates,
+            set(&["base", "header", "macros", "model"])
+        );
+        assert_eq!(
+            inputs.variables,
+            set(&["info.title", "info.version", "item"])
+        );
+        assert_eq!(inputs.filters, set(&["snake_case"]));
+    }
+
+    #[test]
+    fn scopes_locals() {
+        let inputs = inputs(&[(
+            "model",
+            "{% macro name(field) %}{{ field.name }}{{ title }}{% endmacro %}{% for type in ir.types %}{{ type.name }}{% set kind = type.kind %}{% set_global count = 1 %}{% endfor %}{{ type }}{{ kind }}{{ count }}{{ field }}{% set title = info.title %}",
+        )]);
+        assert_eq!(
+            inputs.variables,
+            set(&["field", "info.title", "ir.types", "kind", "title", "type"])
+        );
+    }
+}
@@ -1,24 +1,473 @@
 use anyhow::anyhow;
-use serde::Deserialize;
-use std::fs::read_to_string;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fs::{read_dir, read_to_string},
+    path::{Path, PathBuf},
+};
 
-#[derive(Deserialize, Debug)]
+/// A flavour with its `extends` chain resolved.
+#[derive(Debug)]
 pub struct Flavour {
+    pub name: String,
     pub version: Option<String>,
     pub language: String,
-    #[serde(alias = "template")]
     pub templates: Vec<Template>,
+    /// The files copied verbatim, from `[[asset]]` entries and the `static/` directories along the chain.
+    pub assets: Vec<Asset>,
+    /// The fragments merged into files of the project, in the order of the chain from its root.
+    pub fragments: Vec<Fragment>,
+    /// The options the flavour declares, by name.
+    pub options: BTreeMap<String, FlavourOption>,
+    /// The flavours this flavour is made of, starting with itself and ending with the root of its `extends` chain.
+    pub chain: Vec<String>,
 }
 
+/// The `config.toml` of a flavour.
+///
+/// A flavour may extend another one, e.g. `extends = "axum"`. It inherits its language and template
+/// entries, may drop entries by input with `remove = ["handler"]` and adds its own entries after them.
+/// An entry with the input of inherited ones replaces them in place, e.g. to change their output.
+/// Template files are looked up in the flavour first and then along the chain, so a flavour overrides
+/// a single inherited template by providing a file of the same name.
+/// Options and assets are inherited as well. Redeclaring an option replaces it, and assets are
+/// replaced and removed by input like templates.
 #[derive(Deserialize, Debug)]
+struct Config {
+    version: Option<String>,
+    extends: Option<String>,
+    language: Option<String>,
+    #[serde(default)]
+    remove: Vec<String>,
+    #[serde(alias = "template", default)]
+    templates: Vec<Template>,
+    #[serde(alias = "asset", default)]
+    assets: Vec<Asset>,
+    #[serde(alias = "fragment", default)]
+    fragments: Vec<Fragment>,
+    #[serde(default)]
+    options: BTreeMap<String, FlavourOption>,
+}
+
+/// A setting of a flavour, declared under `[options.<name>]`:
+///
+/// ```toml
+/// [options.error_type]
+/// type = "string"
+/// default = "anyhow::Error"
+/// description = "The error type returned by handlers"
+/// ```
+///
+/// Options without a default must be set by the project.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FlavourOption {
+    pub r#type: OptionType,
+    pub default: Option<toml::Value>,
+    pub description: Option<String>,
+    /// The values a `string` option is restricted to, if any.
+    pub values: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionType {
+    Bool,
+    String,
+    Integer,
+    Float,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Template {
     pub input: String,
     pub output: String,
     pub iteration: Option<String>,
+    #[serde(default)]
+    pub mode: WriteMode,
+    /// A Tera expression deciding whether the template is rendered, e.g. `webhooks` or `"pets" in item.tags`.
+    /// Evaluated once per item for iterated templates.
+    pub when: Option<String>,
+    /// The file the template is loaded from, resolved along the `extends` chain.
+    #[serde(skip)]
+    pub source: PathBuf,
+}
+
+/// A file or directory of a flavour which is copied verbatim rather than rendered:
+///
+/// ```toml
+/// [[asset]]
+/// input = "scripts/build.rs"
+/// output = "{crate_dir}/build.rs"
+/// permissions = 0o755
+/// ```
+///
+/// The output may use the options of the flavour as placeholders, e.g. `{crate_dir}`.
+/// A directory is copied with everything under it. Every file of the flavour's `static/` directory
+/// is an asset as well, written to the same path relative to the project.
+/// Assets go through the same write modes and manifest as rendered files. They are copied byte for
+/// byte, so may be binary, but only UTF-8 text can be appended to or merged into.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Asset {
+    /// The path of the file or directory, relative to the flavour.
+    pub input: String,
+    pub output: String,
+    /// The permission bits the files are given, e.g. `0o755`. Only applied on Unix.
+    pub permissions: Option<u32>,
+    #[serde(default)]
+    pub mode: WriteMode,
+    /// The file or directory the asset is copied from, resolved along the `extends` chain.
+    #[serde(skip)]
+    pub source: PathBuf,
+}
+
+/// A fragment merged into a file of the project, e.g. dependencies the generated code needs:
+///
+/// ```toml
+/// [[fragment]]
+/// output = "Cargo.toml"
+///
+/// [fragment.value.dependencies]
+/// serde = { version = "1", features = ["derive"] }
+/// ```
+///
+/// The value is written in the format of the output, a TOML, JSON or YAML file, and merged into it
+/// like a template with [WriteMode::Merge]. Fragments of the same output are combined, with those of
+/// a flavour taking precedence over those of the flavours it extends. The output may use the options
+/// of the flavour as placeholders.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Fragment {
+    pub output: String,
+    pub value: toml::Table,
+}
+
+/// How a rendered template is written to an output file which already exists.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WriteMode {
+    /// Replace the file, keeping its protected regions.
+    #[default]
+    Overwrite,
+    /// Only create the file, after which it is owned by the user.
+    CreateOnce,
+    /// Append the rendered contents unless the file already contains them.
+    Append,
+    /// Add keys missing in the file, keeping all existing values. Supports JSON, YAML and TOML.
+    Merge,
+}
+
+impl fmt::Display for OptionType {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            OptionType::Bool => "bool",
+            OptionType::String => "string",
+            OptionType::Integer => "integer",
+            OptionType::Float => "float",
+        };
+        formatter.write_str(name)
+    }
+}
+
+impl fmt::Display for WriteMode {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            WriteMode::Overwrite => "overwrite",
+            WriteMode::CreateOnce => "create-once",
+            WriteMode::Append => "append",
+            WriteMode::Merge => "merge",
+        };
+        formatter.write_str(name)
+    }
 }
 
 pub fn get_flavour_config(name: String) -> anyhow::Result<Flavour> {
-    let config_contents = read_to_string(format!(".openapi/flavours/{name}/config.toml"))?;
+    let flavour = read_flavour(name)?;
+    if let Some(template) = flavour
+        .templates
+        .iter()
+        .find(|template| !template.source.exists())
+    {
+        return Err(anyhow!(
+            "Template {} of flavour {} not found in {}",
+            template.input,
+            flavour.name,
+            flavour.chain.join(", ")
+        ));
+    }
+    if let Some(asset) = flavour.assets.iter().find(|asset| !asset.source.exists()) {
+        return Err(anyhow!(
+            "Asset {} of flavour {} not found in {}",
+            asset.input,
+            flavour.name,
+            flavour.chain.join(", ")
+        ));
+    }
+
+    Ok(flavour)
+}
+
+/// Resolves a flavour like [get_flavour_config], without failing on missing template and asset files.
+/// The source of a template or asset which is not found anywhere along the chain is the file it
+/// would have in the flavour itself.
+pub fn read_flavour(name: String) -> anyhow::Result<Flavour> {
+    let mut chain = vec![name.clone()];
+    let mut configs = vec![read_config(&name)?];
+    while let Some(parent) = configs.last().and_then(|config| config.extends.clone()) {
+        if chain.contains(&parent) {
+            return Err(anyhow!(
+                "Flavour {name} extends itself through {} -> {parent}",
+                chain.join(" -> ")
+            ));
+        }
+        configs.push(read_config(&parent)?);
+        chain.push(parent);
+    }
+
+    let mut version = None;
+    let mut language = None;
+    let mut templates: Vec<Template> = Vec::new();
+    let mut assets: Vec<Asset> = Vec::new();
+    let mut fragments = Vec::new();
+    let mut options = BTreeMap::new();
+    // From the root of the chain down to the flavour itself.
+    for config in configs.into_iter().rev() {
+        version = config.version.or(version);
+        language = config.language.or(language);
+        inherit(
+            &mut templates,
+            config.templates,
+            &config.remove,
+            |template| &template.input,
+        );
+        inherit(&mut assets, config.assets, &config.remove, |asset| {
+            &asset.input
+        });
+        fragments.extend(config.fragments);
+        options.extend(config.options);
+    }
+
+    for template in &mut templates {
+        let file = format!("{}.tera", template.input);
+        template.source = chain
+            .iter()
+            .map(|flavour| directory(flavour).join(&file))
+            .find(|source| source.exists())
+            .unwrap_or_else(|| directory(&name).join(&file));
+    }
+    for asset in &mut assets {
+        asset.source = chain
+            .iter()
+            .map(|flavour| directory(flavour).join(&asset.input))
+            .find(|source| source.exists())
+            .unwrap_or_else(|| directory(&name).join(&asset.input));
+    }
+    // Files of a flavour's `static/` directory replace those of the flavours it extends.
+    let mut statics = BTreeMap::new();
+    for flavour in chain.iter().rev() {
+        statics.extend(files(&directory(flavour).join("static"))?);
+    }
+    assets.extend(statics.into_iter().map(|(relative, source)| Asset {
+        input: format!("static/{relative}"),
+        output: relative,
+        permissions: None,
+        mode: WriteMode::default(),
+        source,
+    }));
+
+    Ok(Flavour {
+        language: language.ok_or_else(|| anyhow!("Flavour {name} does not set a language"))?,
+        name,
+        version,
+        templates,
+        assets,
+        fragments,
+        options,
+        chain,
+    })
+}
+
+/// Applies the entries of a flavour to those it inherits: inherited entries with an input in `remove`
+/// are dropped, and those with the input of an own entry are replaced by the own entries with that input,
+/// at the position of the first of them. The remaining own entries follow.
+fn inherit<T>(inherited: &mut Vec<T>, own: Vec<T>, remove: &[String], input: fn(&T) -> &String) {
+    inherited.retain(|entry| !remove.contains(input(entry)));
+    let (mut replacing, added): (Vec<T>, Vec<T>) = own
+        .into_iter()
+        .partition(|entry| inherited.iter().any(|other| input(other) == input(entry)));
+    let replaced: BTreeSet<String> = replacing.iter().map(|entry| input(entry).clone()).collect();
+
+    let mut entries = Vec::new();
+    for entry in inherited.drain(..) {
+        if !replaced.contains(input(&entry)) {
+            entries.push(entry);
+            continue;
+        }
+        // Placed at the first inherited entry with their input, the others are dropped.
+        let (matching, rest): (Vec<T>, Vec<T>) = std::mem::take(&mut replacing)
+            .into_iter()
+            .partition(|candidate| input(candidate) == input(&entry));
+        replacing = rest;
+        entries.extend(matching);
+    }
+    entries.extend(added);
+    *inherited = entries;
+}
+
+/// The partials available to the templates of a flavour, keyed by the name they are registered under.
+///
+/// Files under `.openapi/partials/` are shared by all flavours and named `partials/<path>`.
+/// Files under the `partials/` directory of a flavour, or of a flavour it extends, are named
+/// `<flavour>/partials/<path>`, e.g. `{% import "axum/partials/serde.tera" as serde %}`.
+pub fn partials(flavour: &Flavour) -> anyhow::Result<BTreeMap<String, PathBuf>> {
+    let mut partials = BTreeMap::new();
+    let mut directories = vec![(String::from("partials"), PathBuf::from(".openapi/partials"))];
+    for name in &flavour.chain {
+        directories.push((format!("{name}/partials"), directory(name).join("partials")));
+    }
+
+    for (namespace, root) in directories {
+        for (relative, path) in files(&root)? {
+            partials.insert(format!("{namespace}/{relative}"), path);
+        }
+    }
+
+    Ok(partials)
+}
+
+/// The files under `root`, keyed by their path relative to it with `/` as separator.
+/// A missing directory has no files.
+pub fn files(root: &Path) -> anyhow::Result<BTreeMap<String, PathBuf>> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        let Ok(entries) = read_dir(&directory) else {
+            continue;
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let relative: Vec<_> = path
+                .strip_prefix(root)?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect();
+            files.insert(relative.join("/"), path);
+        }
+    }
+
+    Ok(files)
+}
+
+/// The directory of the flavour `name`.
+pub fn directory(name: &str) -> PathBuf {
+    Path::new(".openapi/flavours").join(name)
+}
+
+fn read_config(name: &str) -> anyhow::Result<Config> {
+    let path = directory(name).join("config.toml");
+    let contents = read_to_string(&path).map_err(|error| {
+        anyhow!(
+            "Could not read flavour {name} at {}: {error}",
+            path.display()
+        )
+    })?;
+
+    toml::from_str(&contents).map_err(|error| anyhow!(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(input: &str, output: &str) -> Template {
+        Template {
+            input: input.to_string(),
+            output: output.to_string(),
+            iteration: None,
+            mode: WriteMode::Overwrite,
+            when: None,
+            source: PathBuf::new(),
+        }
+    }
+
+    fn entries(templates: &[Template]) -> Vec<(&str, &str)> {
+        templates
+            .iter()
+            .map(|template| (template.input.as_str(), template.output.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn replaces_inherited_entries_by_input() {
+        let mut templates = vec![
+            template("model", "src/models/{entity}.rs"),
+            template("handler", "src/handlers.rs"),
+            template("lib", "src/lib.rs"),
+        ];
+        inherit(
+            &mut templates,
+            vec![
+                template("tests", "tests/api.rs"),
+                template("model", "src/{entity}.rs"),
+            ],
+            &[],
+            |template| &template.input,
+        );
+        assert_eq!(
+            entries(&templates),
+            [
+                ("model", "src/{entity}.rs"),
+                ("handler", "src/handlers.rs"),
+                ("lib", "src/lib.rs"),
+                ("tests", "tests/api.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn replaces_every_inherited_entry_of_an_input() {
+        let mut templates = vec![
+            template("model", "src/models/{entity}.rs"),
+            template("lib", "src/lib.rs"),
+            template("model", "src/types/{entity}.rs"),
+        ];
+        inherit(
+            &mut templates,
+            vec![
+                template("model", "src/a/{entity}.rs"),
+                template("model", "src/b/{entity}.rs"),
+            ],
+            &[],
+            |template| &template.input,
+        );
+        assert_eq!(
+            entries(&templates),
+            [
+                ("model", "src/a/{entity}.rs"),
+                ("model", "src/b/{entity}.rs"),
+                ("lib", "src/lib.rs"),
+            ]
+        );
+    }
 
-    toml::from_str(&config_contents).map_err(|error| anyhow!(error))
+    #[test]
+    fn removes_inherited_entries_before_adding_own() {
+        let mut templates = vec![
+            template("model", "src/models/{entity}.rs"),
+            template("lib", "src/lib.rs"),
+        ];
+        inherit(
+            &mut templates,
+            vec![template("model", "src/{entity}.rs")],
+            &[String::from("model")],
+            |template| &template.input,
+        );
+        assert_eq!(
+            entries(&templates),
+            [("lib", "src/lib.rs"), ("model", "src/{entity}.rs")]
+        );
+    }
 }
@@ -0,0 +1,1157 @@
+use anyhow::anyhow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::{
+    naming::{pascal_case, preferred_name, Namer},
+    Body, Discriminator, Ir, Mapping, NamedType, Operation, Parameter, Property, Response, Type,
+};
+use crate::schema::{
+    AdditionalProperties, DiscriminatorObject, MediaTypeObject, OpenAPI, OperationObject,
+    ParameterObject, PathItemObject, ReferenceOr, SchemaObject, SchemaType,
+};
+
+/// How many references are followed before a chain is considered a cycle.
+const MAX_REFERENCE_DEPTH: usize = 32;
+
+/// Lowers an [OpenAPI] document into an [Ir].
+///
+/// Component schemas claim their names first. Afterwards inline schemas are visited in a fixed order
+/// (components, then paths by name and operations by method) and named after their position:
+///
+/// - request bodies become `{Operation}Request`, responses `{Operation}{Status}Response`
+/// - object properties become `{Parent}{Property}`, array items `{Parent}Item`, map values `{Parent}Value`
+/// - `oneOf` and `anyOf` variants become `{Parent}Variant{n}`
+///
+/// A `title` replaces the synthesized name and `x-oam-name` replaces both.
+/// Colliding names get the lowest free numeric suffix, e.g. `Pet2`.
+///
+/// `oneOf` and `anyOf` become unions, tagged if they declare a discriminator.
+/// `allOf` is flattened into a single object which records the types it inherits from.
+pub struct Builder<'a> {
+    document: &'a OpenAPI,
+    namer: Namer,
+    types: BTreeMap<String, NamedType>,
+    /// The component schemas inheriting from a component schema through `allOf`, keyed by the parent.
+    subtypes: HashMap<String, Vec<String>>,
+    /// The component schemas currently being flattened, to detect cyclic inheritance.
+    flattening: HashSet<String>,
+}
+
+/// A type a discriminator selects without an explicit mapping.
+struct Implicit {
+    /// The component schema of the type, which explicit mappings may refer to.
+    key: Option<String>,
+    value: String,
+    name: String,
+}
+
+impl<'a> Builder<'a> {
+    pub fn new(document: &'a OpenAPI) -> Self {
+        Builder {
+            document,
+            namer: Namer::default(),
+            types: BTreeMap::new(),
+            subtypes: HashMap::new(),
+            flattening: HashSet::new(),
+        }
+    }
+
+    pub fn build(mut self) -> anyhow::Result<Ir> {
+        let document = self.document;
+        let schemas = document
+            .components
+            .as_ref()
+            .and_then(|components| components.schemas.as_ref());
+
+        if let Some(schemas) = schemas {
+            for key in sorted_keys(schemas) {
+                let desired = schemas[key].oam_name.as_deref().unwrap_or(key);
+                self.namer.claim(&component_origin(key), desired);
+
+                for part in schemas[key].all_of.iter().flatten() {
+                    let parent = part
+                        .reference
+                        .as_deref()
+                        .and_then(|reference| component_key(reference, "schemas"));
+                    if let Some(parent) = parent {
+                        self.subtypes.entry(parent).or_default().push(key.clone());
+                    }
+                }
+            }
+            for key in sorted_keys(schemas) {
+                let origin = component_origin(key);
+                let name = self.namer.claim(&origin, key);
+                if !self.types.contains_key(&name) {
+                    self.define(name.clone(), origin.clone(), &schemas[key], true)?;
+                }
+
+                // A discriminator next to `oneOf` or `anyOf` tags the union, otherwise it tags the subtypes.
+                let schema = &schemas[key];
+                if schema.one_of.is_some() || schema.any_of.is_some() {
+                    continue;
+                }
+                if let Some(discriminator) = &schema.discriminator {
+                    let subtypes = self
+                        .subtypes
+                        .get(key)
+                        .into_iter()
+                        .flatten()
+                        .map(|subtype| self.component(subtype, &origin))
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    let resolved = self.discriminator(discriminator, &subtypes, &origin)?;
+                    if let Some(Type::Object { discriminator, .. }) = self
+                        .types
+                        .get_mut(&name)
+                        .map(|named| strip_nullable(&mut named.schema))
+                    {
+                        *discriminator = Some(resolved);
+                    }
+                }
+            }
+        }
+
+        let mut operations = Vec::new();
+        if let Some(paths) = &document.paths {
+            for path in sorted_keys(paths) {
+                for (method, operation) in paths[path].operations() {
+                    operations.push(self.operation(path, &paths[path], method, operation)?);
+                }
+            }
+        }
+
+        Ok(Ir {
+            types: self.types,
+            operations,
+        })
+    }
+
+    /// Adds the named type `name` described by `schema`.
+    fn define(
+        &mut self,
+        name: String,
+        origin: String,
+        schema: &SchemaObject,
+        keep_nullable: bool,
+    ) -> anyhow::Result<()> {
+        let mut shape = self.shape(&name, &origin, schema)?;
+        if keep_nullable {
+            shape = nullable(schema, shape);
+        }
+
+        self.types.insert(
+            name.clone(),
+            NamedType {
+                name,
+                origin,
+                description: schema.description.clone(),
+                schema: shape,
+            },
+        );
+        Ok(())
+    }
+
+    /// The type of a schema used in place, e.g. as a property, parameter or body.
+    /// Schemas which need a declaration of their own are hoisted and referenced by name.
+    fn slot(
+        &mut self,
+        suggested: &str,
+        origin: &str,
+        schema: &SchemaObject,
+    ) -> anyhow::Result<Type> {
+        if schema.reference.is_none() && needs_name(schema) {
+            let name = self.namer.claim(origin, &preferred_name(schema, suggested));
+            if !self.types.contains_key(&name) {
+                self.define(name.clone(), origin.to_string(), schema, false)?;
+            }
+            return Ok(nullable(schema, Type::Reference { name }));
+        }
+
+        let shape = self.shape(suggested, origin, schema)?;
+        Ok(nullable(schema, shape))
+    }
+
+    /// The shape of a schema, ignoring whether it is nullable.
+    /// Inline schemas nested in it are named after `name`.
+    fn shape(&mut self, name: &str, origin: &str, schema: &SchemaObject) -> anyhow::Result<Type> {
+        if let Some(reference) = &schema.reference {
+            return self.reference(reference, origin);
+        }
+        if let Some(variants) = &schema.one_of {
+            return self.union(name, origin, schema, "oneOf", variants);
+        }
+        if let Some(variants) = &schema.any_of {
+            return self.union(name, origin, schema, "anyOf", variants);
+        }
+        if let Some(parts) = &schema.all_of {
+            return self.all_of(name, origin, schema, parts);
+        }
+        if let Some(values) = &schema.r#enum {
+            return Ok(Type::Enum {
+                values: values.clone(),
+            });
+        }
+
+        match primary_type(schema).as_deref() {
+            Some("object") => self.object(name, origin, schema),
+            Some("array") => {
+                let items = match &schema.items {
+                    Some(items) => {
+                        self.slot(&format!("{name}Item"), &format!("{origin}.items"), items)?
+                    }
+                    None => Type::Any,
+                };
+                Ok(Type::Array {
+                    items: Box::new(items),
+                })
+            }
+            Some(primitive) => Ok(Type::Primitive {
+                r#type: primitive.to_string(),
+                format: schema.format.clone(),
+            }),
+            None => Ok(Type::Any),
+        }
+    }
+
+    fn object(&mut self, name: &str, origin: &str, schema: &SchemaObject) -> anyhow::Result<Type> {
+        let additional_properties = match &schema.additional_properties {
+            Some(AdditionalProperties::Schema(values)) => Some(self.slot(
+                &format!("{name}Value"),
+                &format!("{origin}.additionalProperties"),
+                values,
+            )?),
+            Some(AdditionalProperties::Allowed(false)) => None,
+            _ if schema.properties.is_none() => Some(Type::Any),
+            _ => None,
+        };
+
+        let Some(properties) = &schema.properties else {
+            if let Some(values) = additional_properties {
+                return Ok(Type::Map {
+                    values: Box::new(values),
+                });
+            }
+            return Ok(Type::Object {
+                properties: Vec::new(),
+                additional_properties: None,
+                extends: Vec::new(),
+                discriminator: None,
+            });
+        };
+
+        let required = schema.required.clone().unwrap_or_default();
+        let mut lowered = Vec::new();
+        for key in sorted_keys(properties) {
+            let property = &properties[key];
+            lowered.push(Property {
+                name: key.clone(),
+                required: required.contains(key),
+                description: property.description.clone(),
+                schema: self.slot(
+                    &format!("{name}{}", pascal_case(key)),
+                    &format!("{origin}.properties.{key}"),
+                    property,
+                )?,
+                inherited_from: None,
+            });
+        }
+
+        Ok(Type::Object {
+            properties: lowered,
+            additional_properties: additional_properties.map(Box::new),
+            extends: Vec::new(),
+            discriminator: None,
+        })
+    }
+
+    /// Lowers `oneOf` or `anyOf`. A `null` variant makes the union nullable,
+    /// and a union of a single other variant collapses into that variant.
+    fn union(
+        &mut self,
+        name: &str,
+        origin: &str,
+        schema: &SchemaObject,
+        keyword: &str,
+        variants: &[SchemaObject],
+    ) -> anyhow::Result<Type> {
+        let mut lowered = Vec::new();
+        let mut implicit = Vec::new();
+        for (index, variant) in variants.iter().enumerate() {
+            if is_null(variant) {
+                continue;
+            }
+            let variant_origin = format!("{origin}.{keyword}.{index}");
+            lowered.push(self.slot(
+                &format!("{name}Variant{}", index + 1),
+                &variant_origin,
+                variant,
+            )?);
+            implicit.push((variant, variant_origin));
+        }
+
+        let shape = match &schema.discriminator {
+            None if lowered.len() == 1 => lowered.remove(0),
+            None => Type::Union {
+                variants: lowered,
+                exclusive: keyword == "oneOf",
+                discriminator: None,
+            },
+            Some(discriminator) => {
+                let implicit = implicit
+                    .into_iter()
+                    .zip(&lowered)
+                    .map(|((variant, variant_origin), lowered)| {
+                        self.implicit(discriminator, variant, lowered, &variant_origin)
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Type::Union {
+                    variants: lowered,
+                    exclusive: keyword == "oneOf",
+                    discriminator: Some(self.discriminator(discriminator, &implicit, origin)?),
+                }
+            }
+        };
+
+        if variants.iter().any(is_null) {
+            return Ok(Type::Nullable {
+                inner: Box::new(shape),
+            });
+        }
+        Ok(shape)
+    }
+
+    /// Flattens `allOf` into a single object.
+    /// Properties of later parts and of the schema itself override inherited ones with the same name.
+    fn all_of(
+        &mut self,
+        name: &str,
+        origin: &str,
+        schema: &SchemaObject,
+        parts: &[SchemaObject],
+    ) -> anyhow::Result<Type> {
+        if is_alias(schema) {
+            return self.shape(name, origin, &parts[0]);
+        }
+
+        let mut properties = Vec::new();
+        let mut additional_properties = None;
+        let mut extends = Vec::new();
+        let mut required: Vec<String> = schema.required.clone().unwrap_or_default();
+        let mut others = Vec::new();
+
+        for (index, part) in parts.iter().enumerate() {
+            let part_origin = format!("{origin}.allOf.{index}");
+            let (mut shape, parent) = match &part.reference {
+                Some(reference) => {
+                    let parent = self.parent(reference, &part_origin)?;
+                    (self.types[&parent].schema.clone(), Some(parent))
+                }
+                None => {
+                    required.extend(part.required.iter().flatten().cloned());
+                    (self.shape(name, &part_origin, part)?, None)
+                }
+            };
+
+            match strip_nullable(&mut shape) {
+                Type::Object {
+                    properties: inherited,
+                    additional_properties: extra,
+                    ..
+                } => {
+                    for mut property in inherited.drain(..) {
+                        if property.inherited_from.is_none() {
+                            property.inherited_from.clone_from(&parent);
+                        }
+                        merge_property(&mut properties, property);
+                    }
+                    if extra.is_some() {
+                        additional_properties = extra.take();
+                    }
+                    extends.extend(parent);
+                }
+                // Parts without a type only add constraints.
+                Type::Any => {}
+                _ => others.push(match parent {
+                    Some(parent) => Type::Reference { name: parent },
+                    None => shape,
+                }),
+            }
+        }
+
+        if schema.properties.is_some() || schema.additional_properties.is_some() {
+            if let Type::Object {
+                properties: own,
+                additional_properties: extra,
+                ..
+            } = self.object(name, origin, schema)?
+            {
+                for property in own {
+                    merge_property(&mut properties, property);
+                }
+                if extra.is_some() {
+                    additional_properties = extra;
+                }
+            }
+        }
+
+        if properties.is_empty() && extends.is_empty() && others.len() == 1 {
+            return Ok(others.remove(0));
+        }
+
+        for property in properties.iter_mut() {
+            if required.contains(&property.name) {
+                property.required = true;
+            }
+        }
+
+        Ok(Type::Object {
+            properties,
+            additional_properties,
+            extends,
+            discriminator: None,
+        })
+    }
+
+    /// Resolves the parent referenced by an `allOf` part, defining it first if needed.
+    fn parent(&mut self, reference: &str, origin: &str) -> anyhow::Result<String> {
+        let Type::Reference { name } = self.reference(reference, origin)? else {
+            unreachable!("references to component schemas always resolve to a named type");
+        };
+        if self.types.contains_key(&name) {
+            return Ok(name);
+        }
+
+        if !self.flattening.insert(name.clone()) {
+            return Err(anyhow!("Cyclic allOf inheritance of {name} at {origin}"));
+        }
+        let document = self.document;
+        let key = component_key(reference, "schemas").unwrap_or_default();
+        let schema = document
+            .components
+            .as_ref()
+            .and_then(|components| components.schemas.as_ref())
+            .and_then(|schemas| schemas.get(&key))
+            .ok_or_else(|| anyhow!("Could not resolve reference {reference} at {origin}"))?;
+        self.define(name.clone(), component_origin(&key), schema, true)?;
+        self.flattening.remove(&name);
+
+        Ok(name)
+    }
+
+    /// Resolves the mapping of a discriminator to named types.
+    /// Types listed in `implicit` are mapped by their value unless mapped explicitly.
+    fn discriminator(
+        &self,
+        discriminator: &DiscriminatorObject,
+        implicit: &[Implicit],
+        origin: &str,
+    ) -> anyhow::Result<Discriminator> {
+        let mut explicit = Vec::new();
+        for value in sorted_keys(&discriminator.mapping) {
+            let target = &discriminator.mapping[value];
+            // Mappings either hold a reference or the name of a component schema.
+            let key = component_key(target, "schemas").unwrap_or_else(|| target.clone());
+            let name = self.namer.get(&component_origin(&key)).ok_or_else(|| {
+                anyhow!("Could not resolve discriminator mapping {value}: {target} at {origin}")
+            })?;
+            explicit.push((value.clone(), key, name.clone()));
+        }
+
+        let mut mapping = Vec::new();
+        for candidate in implicit {
+            let mut mapped = explicit
+                .iter()
+                .filter(|(_, target, _)| candidate.key.as_ref() == Some(target))
+                .peekable();
+            if mapped.peek().is_none() {
+                mapping.push(Mapping {
+                    value: candidate.value.clone(),
+                    name: candidate.name.clone(),
+                });
+            }
+            for (value, _, name) in mapped {
+                mapping.push(Mapping {
+                    value: value.clone(),
+                    name: name.clone(),
+                });
+            }
+        }
+        for (value, key, name) in explicit {
+            if !implicit
+                .iter()
+                .any(|candidate| candidate.key.as_ref() == Some(&key))
+            {
+                mapping.push(Mapping { value, name });
+            }
+        }
+
+        Ok(Discriminator {
+            property: discriminator.property_name.clone(),
+            mapping,
+        })
+    }
+
+    /// How the variant of a discriminated union is mapped unless mapped explicitly.
+    /// A reference to a component schema is mapped by the name of the schema. An inline variant is
+    /// mapped by the value of the discriminator property, which it must declare as a single-value `enum`.
+    fn implicit(
+        &self,
+        discriminator: &DiscriminatorObject,
+        variant: &SchemaObject,
+        lowered: &Type,
+        origin: &str,
+    ) -> anyhow::Result<Implicit> {
+        if let Some(reference) = &variant.reference {
+            let key = component_key(reference, "schemas").ok_or_else(|| {
+                anyhow!("Variant at {origin} of a discriminated union must reference a component schema")
+            })?;
+            return self.component(&key, origin);
+        }
+
+        let property = &discriminator.property_name;
+        let value = variant
+            .properties
+            .as_ref()
+            .and_then(|properties| properties.get(property))
+            .and_then(|property| property.r#enum.as_deref())
+            .and_then(|values| match values {
+                [value] => value.as_str(),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                anyhow!("Inline variant at {origin} of a discriminated union must declare {property} as an enum of a single string, or reference a component schema")
+            })?;
+        let name = match lowered {
+            Type::Reference { name } => name,
+            Type::Nullable { inner } => match inner.as_ref() {
+                Type::Reference { name } => name,
+                _ => unreachable!("inline objects are always hoisted"),
+            },
+            _ => unreachable!("inline objects are always hoisted"),
+        };
+        Ok(Implicit {
+            key: None,
+            value: value.to_string(),
+            name: name.clone(),
+        })
+    }
+
+    /// The implicit mapping of the component schema `key`, by its name.
+    fn component(&self, key: &str, origin: &str) -> anyhow::Result<Implicit> {
+        let name = self
+            .namer
+            .get(&component_origin(key))
+            .ok_or_else(|| anyhow!("Could not resolve {key} at {origin}"))?;
+        Ok(Implicit {
+            key: Some(key.to_string()),
+            value: key.to_string(),
+            name: name.clone(),
+        })
+    }
+
+    fn reference(&self, reference: &str, origin: &str) -> anyhow::Result<Type> {
+        component_key(reference, "schemas")
+            .and_then(|key| self.namer.get(&component_origin(&key)))
+            .map(|name| Type::Reference { name: name.clone() })
+            .ok_or_else(|| anyhow!("Could not resolve reference {reference} at {origin}"))
+    }
+
+    fn operation(
+        &mut self,
+        path: &str,
+        item: &PathItemObject,
+        method: &str,
+        operation: &OperationObject,
+    ) -> anyhow::Result<Operation> {
+        let id = operation
+            .operation_id
+            .clone()
+            .unwrap_or_else(|| format!("{method}{}", pascal_case(path)));
+        let name = pascal_case(&id);
+        let origin = format!("paths.{path}.{method}");
+        let document = self.document;
+        let components = document.components.as_ref();
+
+        // Operation parameters override path parameters with the same name and location.
+        let mut declared = Vec::new();
+        for (index, parameter) in item.parameters.iter().flatten().enumerate() {
+            declared.push((parameter, format!("paths.{path}.parameters.{index}")));
+        }
+        for (index, parameter) in operation.parameters.iter().flatten().enumerate() {
+            declared.push((parameter, format!("{origin}.parameters.{index}")));
+        }
+        let mut parameters: Vec<(&ParameterObject, String)> = Vec::new();
+        for (parameter, parameter_origin) in declared {
+            let resolved = resolve(
+                parameter,
+                parameter_origin,
+                "parameters",
+                components.and_then(|components| components.parameters.as_ref()),
+            )?;
+            parameters.retain(|(existing, _)| {
+                existing.name != resolved.0.name || existing.r#in != resolved.0.r#in
+            });
+            parameters.push((resolved.0, resolved.1));
+        }
+        let parameters = parameters
+            .into_iter()
+            .map(|(parameter, parameter_origin)| {
+                let schema = match &parameter.schema {
+                    Some(schema) => self.slot(
+                        &format!("{name}{}Parameter", pascal_case(&parameter.name)),
+                        &format!("{parameter_origin}.schema"),
+                        schema,
+                    )?,
+                    None => Type::Any,
+                };
+                Ok(Parameter {
+                    name: parameter.name.clone(),
+                    location: parameter.r#in.clone(),
+                    required: parameter.required.unwrap_or(false),
+                    description: parameter.description.clone(),
+                    schema,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let request_body = match &operation.request_body {
+            Some(request_body) => {
+                let (request_body, body_origin, key) = resolve(
+                    request_body,
+                    format!("{origin}.requestBody"),
+                    "requestBodies",
+                    components.and_then(|components| components.request_bodies.as_ref()),
+                )?;
+                let suggested = format!(
+                    "{}Request",
+                    key.as_deref().map_or(name.clone(), pascal_case)
+                );
+                self.body(
+                    &suggested,
+                    &body_origin,
+                    &request_body.content,
+                    request_body.required.unwrap_or(false),
+                    request_body.description.clone(),
+                )?
+            }
+            None => None,
+        };
+
+        let mut responses = Vec::new();
+        if let Some(declared) = &operation.responses {
+            for status in sorted_keys(declared) {
+                let (response, response_origin, key) = resolve(
+                    &declared[status],
+                    format!("{origin}.responses.{status}"),
+                    "responses",
+                    components.and_then(|components| components.responses.as_ref()),
+                )?;
+                let suggested = match key {
+                    Some(key) => format!("{}Response", pascal_case(&key)),
+                    None => format!("{name}{}Response", pascal_case(status)),
+                };
+                let body = match &response.content {
+                    Some(content) => {
+                        self.body(&suggested, &response_origin, content, true, None)?
+                    }
+                    None => None,
+                };
+                responses.push(Response {
+                    status: status.clone(),
+                    description: response.description.clone(),
+                    body,
+                });
+            }
+        }
+
+        Ok(Operation {
+            id,
+            name,
+            method: method.to_string(),
+            path: path.to_string(),
+            summary: operation.summary.clone(),
+            description: operation.description.clone(),
+            tags: operation.tags.clone().unwrap_or_default(),
+            deprecated: operation.deprecated.unwrap_or(false),
+            parameters,
+            request_body,
+            responses,
+        })
+    }
+
+    fn body(
+        &mut self,
+        suggested: &str,
+        origin: &str,
+        content: &HashMap<String, MediaTypeObject>,
+        required: bool,
+        description: Option<String>,
+    ) -> anyhow::Result<Option<Body>> {
+        let content_type = if content.contains_key("application/json") {
+            "application/json"
+        } else {
+            match sorted_keys(content).into_iter().next() {
+                Some(content_type) => content_type.as_str(),
+                None => return Ok(None),
+            }
+        };
+
+        let schema = match &content[content_type].schema {
+            Some(schema) => self.slot(
+                suggested,
+                &format!("{origin}.content.{content_type}.schema"),
+                schema,
+            )?,
+            None => Type::Any,
+        };
+
+        Ok(Some(Body {
+            content_type: content_type.to_string(),
+            required,
+            description,
+            schema,
+        }))
+    }
+}
+
+/// Whether a schema needs a declaration of its own when used inline.
+fn needs_name(schema: &SchemaObject) -> bool {
+    schema.properties.is_some()
+        || schema.r#enum.is_some()
+        || ((schema.one_of.is_some() || schema.any_of.is_some() || schema.all_of.is_some())
+            && !is_alias(schema))
+        || (primary_type(schema).as_deref() == Some("object")
+            && matches!(
+                schema.additional_properties,
+                Some(AdditionalProperties::Allowed(false))
+            ))
+}
+
+/// The type of a schema other than `null`, inferred from its keywords if not declared.
+/// Schemas allowing several types other than `null` have no primary type.
+fn primary_type(schema: &SchemaObject) -> Option<String> {
+    match &schema.r#type {
+        Some(SchemaType::Single(r#type)) if r#type != "null" => Some(r#type.clone()),
+        Some(SchemaType::Multiple(types)) => {
+            let mut types = types.iter().filter(|r#type| *r#type != "null");
+            match (types.next(), types.next()) {
+                (Some(r#type), None) => Some(r#type.clone()),
+                _ => None,
+            }
+        }
+        Some(_) => None,
+        None if schema.properties.is_some() || schema.additional_properties.is_some() => {
+            Some(String::from("object"))
+        }
+        None if schema.items.is_some() => Some(String::from("array")),
+        None => None,
+    }
+}
+
+/// Whether a composition merely wraps a single other schema,
+/// e.g. `anyOf: [$ref, {type: "null"}]` or `allOf: [$ref]` next to a description.
+fn is_alias(schema: &SchemaObject) -> bool {
+    if schema.properties.is_some() || schema.discriminator.is_some() {
+        return false;
+    }
+
+    let single = |variants: &Vec<SchemaObject>| {
+        variants.iter().filter(|variant| !is_null(variant)).count() == 1
+    };
+    match (&schema.one_of, &schema.any_of, &schema.all_of) {
+        (Some(variants), None, None) | (None, Some(variants), None) => single(variants),
+        (None, None, Some(parts)) => parts.len() == 1 && parts[0].reference.is_some(),
+        _ => false,
+    }
+}
+
+fn is_null(schema: &SchemaObject) -> bool {
+    matches!(&schema.r#type, Some(SchemaType::Single(r#type)) if r#type == "null")
+}
+
+/// Replaces a property with the same name in place, or appends it.
+fn merge_property(properties: &mut Vec<Property>, property: Property) {
+    match properties
+        .iter_mut()
+        .find(|existing| existing.name == property.name)
+    {
+        Some(existing) => *existing = property,
+        None => properties.push(property),
+    }
+}
+
+fn strip_nullable(shape: &mut Type) -> &mut Type {
+    match shape {
+        Type::Nullable { inner } => inner,
+        shape => shape,
+    }
+}
+
+fn nullable(schema: &SchemaObject, shape: Type) -> Type {
+    let is_nullable = schema.nullable == Some(true)
+        || matches!(&schema.r#type, Some(SchemaType::Multiple(types)) if types.iter().any(|r#type| r#type == "null"));
+
+    if is_nullable {
+        Type::Nullable {
+            inner: Box::new(shape),
+        }
+    } else {
+        shape
+    }
+}
+
+/// Follows `#/components/{section}/{key}` references until a value is found.
+/// Returns the value, its origin and the key of the component it was found in, if any.
+fn resolve<'b, T>(
+    item: &'b ReferenceOr<T>,
+    origin: String,
+    section: &str,
+    components: Option<&'b HashMap<String, ReferenceOr<T>>>,
+) -> anyhow::Result<(&'b T, String, Option<String>)> {
+    let mut current = item;
+    let mut origin = origin;
+    let mut key = None;
+
+    for _ in 0..MAX_REFERENCE_DEPTH {
+        match current {
+            ReferenceOr::Value(value) => return Ok((value, origin, key)),
+            ReferenceOr::Reference(reference) => {
+                let name = component_key(&reference.reference, section);
+                current = name
+                    .as_ref()
+                    .and_then(|name| components.and_then(|components| components.get(name)))
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Could not resolve reference {} at {origin}",
+                            reference.reference
+                        )
+                    })?;
+                origin = format!(
+                    "components.{section}.{}",
+                    name.as_deref().unwrap_or_default()
+                );
+                key = name;
+            }
+        }
+    }
+
+    Err(anyhow!("Reference cycle detected at {origin}"))
+}
+
+/// Extracts `Pet` from `#/components/schemas/Pet`, unescaping JSON pointer characters.
+fn component_key(reference: &str, section: &str) -> Option<String> {
+    reference
+        .strip_prefix(&format!("#/components/{section}/"))
+        .map(|key| key.replace("~1", "/").replace("~0", "~"))
+}
+
+fn component_origin(key: &str) -> String {
+    format!("components.schemas.{key}")
+}
+
+fn sorted_keys<T>(map: &HashMap<String, T>) -> Vec<&String> {
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(yaml: &str) -> anyhow::Result<Ir> {
+        let document: OpenAPI = serde_yaml::from_str(yaml).unwrap();
+        Builder::new(&document).build()
+    }
+
+    fn names(ir: &Ir) -> Vec<&str> {
+        ir.types.keys().map(String::as_str).collect()
+    }
+
+    const PETS: &str = r#"
+openapi: 3.1.0
+info: { title: Pets, version: "1" }
+paths:
+  /pets:
+    post:
+      operationId: createPets
+      requestBody:
+        content:
+          application/json:
+            schema:
+              type: object
+              properties:
+                name: { type: string }
+      responses:
+        "201":
+          description: Created
+          content:
+            application/json:
+              schema:
+                type: object
+                properties:
+                  id: { type: integer }
+components:
+  schemas:
+    PetOwner:
+      type: object
+      properties:
+        address:
+          type: object
+          properties:
+            street: { type: string }
+        pets:
+          type: array
+          items:
+            type: object
+            properties:
+              name: { type: string }
+"#;
+
+    #[test]
+    fn names_inline_schemas_after_their_position() {
+        let ir = build(PETS).unwrap();
+        assert_eq!(
+            names(&ir),
+            [
+                "CreatePets201Response",
+                "CreatePetsRequest",
+                "PetOwner",
+                "PetOwnerAddress",
+                "PetOwnerPetsItem",
+            ]
+        );
+        assert_eq!(
+            ir.types["PetOwnerAddress"].origin,
+            "components.schemas.PetOwner.properties.address"
+        );
+    }
+
+    #[test]
+    fn names_are_deterministic() {
+        let first = build(PETS).unwrap();
+        for _ in 0..10 {
+            let ir = build(PETS).unwrap();
+            assert_eq!(names(&ir), names(&first));
+            for (name, named) in &ir.types {
+                assert_eq!(named.origin, first.types[name].origin);
+            }
+        }
+    }
+
+    #[test]
+    fn suffixes_names_colliding_with_components() {
+        let ir = build(
+            r#"
+openapi: 3.1.0
+info: { title: Pets, version: "1" }
+paths: {}
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        owner: { type: object, properties: { name: { type: string } } }
+    PetOwner:
+      type: object
+      properties:
+        name: { type: string }
+"#,
+        )
+        .unwrap();
+        assert_eq!(names(&ir), ["Pet", "PetOwner", "PetOwner2"]);
+        assert_eq!(ir.types["PetOwner"].origin, "components.schemas.PetOwner");
+        assert_eq!(
+            ir.types["PetOwner2"].origin,
+            "components.schemas.Pet.properties.owner"
+        );
+    }
+
+    #[test]
+    fn prefers_oam_name_over_title() {
+        let ir = build(
+            r#"
+openapi: 3.1.0
+info: { title: Pets, version: "1" }
+paths: {}
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        owner: { type: object, title: keeper, properties: { name: { type: string } } }
+        vet: { type: object, title: doctor, x-oam-name: Veterinarian, properties: { name: { type: string } } }
+    Owner:
+      x-oam-name: Person
+      type: object
+      properties:
+        name: { type: string }
+"#,
+        )
+        .unwrap();
+        assert_eq!(names(&ir), ["Keeper", "Person", "Pet", "Veterinarian"]);
+    }
+
+    fn mapping(discriminator: &Option<Discriminator>) -> Vec<(&str, &str)> {
+        discriminator
+            .iter()
+            .flat_map(|discriminator| &discriminator.mapping)
+            .map(|mapping| (mapping.value.as_str(), mapping.name.as_str()))
+            .collect()
+    }
+
+    const ANIMALS: &str = r##"
+openapi: 3.1.0
+info: { title: Pets, version: "1" }
+paths: {}
+components:
+  schemas:
+    Pet:
+      oneOf:
+        - $ref: "#/components/schemas/Cat"
+        - $ref: "#/components/schemas/Dog"
+      discriminator:
+        propertyName: kind
+        mapping:
+          doggo: "#/components/schemas/Dog"
+    Animal:
+      type: object
+      properties:
+        kind: { type: string }
+      discriminator:
+        propertyName: kind
+    Cat:
+      allOf:
+        - $ref: "#/components/schemas/Animal"
+        - type: object
+          properties:
+            lives: { type: integer }
+    Dog:
+      allOf:
+        - $ref: "#/components/schemas/Animal"
+        - type: object
+          properties:
+            good: { type: boolean }
+"##;
+
+    #[test]
+    fn maps_discriminated_unions_implicitly() {
+        let ir = build(ANIMALS).unwrap();
+        let Type::Union {
+            variants,
+            exclusive,
+            discriminator,
+        } = &ir.types["Pet"].schema
+        else {
+            panic!("Pet is not a union");
+        };
+        assert!(exclusive);
+        assert_eq!(variants.len(), 2);
+        assert_eq!(discriminator.as_ref().unwrap().property, "kind");
+        assert_eq!(mapping(discriminator), [("Cat", "Cat"), ("doggo", "Dog")]);
+    }
+
+    #[test]
+    fn maps_subtypes_implicitly() {
+        let ir = build(ANIMALS).unwrap();
+        let Type::Object { discriminator, .. } = &ir.types["Animal"].schema else {
+            panic!("Animal is not an object");
+        };
+        assert_eq!(mapping(discriminator), [("Cat", "Cat"), ("Dog", "Dog")]);
+
+        let Type::Object {
+            properties,
+            extends,
+            ..
+        } = &ir.types["Dog"].schema
+        else {
+            panic!("Dog is not an object");
+        };
+        assert_eq!(extends, &["Animal"]);
+        assert_eq!(properties[0].name, "kind");
+        assert_eq!(properties[0].inherited_from.as_deref(), Some("Animal"));
+        assert_eq!(properties[1].name, "good");
+    }
+
+    #[test]
+    fn maps_inline_variants_by_their_enum() {
+        let ir = build(
+            r#"
+openapi: 3.1.0
+info: { title: Pets, version: "1" }
+paths: {}
+components:
+  schemas:
+    Pet:
+      oneOf:
+        - type: object
+          properties:
+            kind: { type: string, enum: [cat] }
+        - type: object
+          title: dog
+          properties:
+            kind: { type: string, enum: [dog] }
+      discriminator:
+        propertyName: kind
+"#,
+        )
+        .unwrap();
+        let Type::Union { discriminator, .. } = &ir.types["Pet"].schema else {
+            panic!("Pet is not a union");
+        };
+        assert_eq!(
+            mapping(discriminator),
+            [("cat", "PetVariant1"), ("dog", "Dog")]
+        );
+    }
+
+    #[test]
+    fn rejects_inline_variants_without_value() {
+        let error = build(
+            r#"
+openapi: 3.1.0
+info: { title: Pets, version: "1" }
+paths: {}
+components:
+  schemas:
+    Pet:
+      oneOf:
+        - type: object
+          properties:
+            kind: { type: string }
+      discriminator:
+        propertyName: kind
+"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Inline variant at components.schemas.Pet.oneOf.0"));
+    }
+
+    #[test]
+    fn rejects_cyclic_all_of() {
+        let error = build(
+            r##"
+openapi: 3.1.0
+info: { title: Pets, version: "1" }
+paths: {}
+components:
+  schemas:
+    Cat:
+      allOf:
+        - $ref: "#/components/schemas/Dog"
+        - type: object
+          properties:
+            lives: { type: integer }
+    Dog:
+      allOf:
+        - $ref: "#/components/schemas/Cat"
+        - type: object
+          properties:
+            good: { type: boolean }
+"##,
+        )
+        .unwrap_err();
+        assert!(error.to_string().starts_with("Cyclic allOf inheritance"));
+    }
+}
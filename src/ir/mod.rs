@@ -0,0 +1,178 @@
+mod builder;
+mod naming;
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::schema::OpenAPI;
+
+/// A language-agnostic view of an OpenAPI document which generators render from.
+/// Inline objects, enums and compositions are hoisted into named [`Ir::types`],
+/// so a generator only ever has to emit named types and refer to them by name.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Ir {
+    /// All named types, keyed by their name.
+    pub types: BTreeMap<String, NamedType>,
+    /// All operations, ordered by path and method.
+    pub operations: Vec<Operation>,
+}
+
+/// A type which generators emit as a declaration of its own.
+#[derive(Serialize, Debug, Clone)]
+pub struct NamedType {
+    /// The name of the type, either taken from the document or synthesized from its position.
+    pub name: String,
+    /// Where the schema of the type lives in the document, e.g. `components.schemas.Pet`.
+    pub origin: String,
+    /// A description of the type.
+    pub description: Option<String>,
+    /// The shape of the type.
+    pub schema: Type,
+}
+
+/// The shape of a value.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Type {
+    /// A `string`, `integer`, `number` or `boolean`, refined by an optional format such as `int64` or `uuid`.
+    Primitive {
+        r#type: String,
+        format: Option<String>,
+    },
+    /// A list of values.
+    Array { items: Box<Type> },
+    /// An object with arbitrary keys.
+    Map { values: Box<Type> },
+    /// An object with known properties.
+    /// Properties of schemas combined with `allOf` are flattened into it, parents listed in `extends`.
+    Object {
+        properties: Vec<Property>,
+        additional_properties: Option<Box<Type>>,
+        /// The named types whose properties were inherited through `allOf`, in order.
+        extends: Vec<String>,
+        /// Set on a base type whose subtypes are told apart by a property of the payload.
+        discriminator: Option<Discriminator>,
+    },
+    /// One of a fixed set of values.
+    Enum { values: Vec<serde_yaml::Value> },
+    /// A value matching one of the variants, lowered from `oneOf` or `anyOf`.
+    /// Unions with a `discriminator` are tagged, all others are untagged.
+    Union {
+        variants: Vec<Type>,
+        /// Whether the value matches exactly one variant (`oneOf`) rather than at least one (`anyOf`).
+        exclusive: bool,
+        discriminator: Option<Discriminator>,
+    },
+    /// A reference to a named type.
+    #[serde(rename = "ref")]
+    Reference { name: String },
+    /// A value which may be `null`.
+    Nullable { inner: Box<Type> },
+    /// Any value.
+    Any,
+}
+
+/// A property of an object.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Property {
+    /// The name of the property as it appears in the payload.
+    pub name: String,
+    /// Whether the property MUST be present.
+    pub required: bool,
+    /// A description of the property.
+    pub description: Option<String>,
+    /// The type of the property.
+    pub schema: Type,
+    /// The named type this property was inherited from through `allOf`, if any.
+    pub inherited_from: Option<String>,
+}
+
+/// Tells the variants of a union or the subtypes of a base type apart.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Discriminator {
+    /// The name of the property in the payload that holds the discriminator value.
+    pub property: String,
+    /// The discriminator values and the types they select, including implicit mappings.
+    pub mapping: Vec<Mapping>,
+}
+
+/// A single discriminator value and the type it selects.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Mapping {
+    /// The value of the discriminator property.
+    pub value: String,
+    /// The name of the selected type.
+    pub name: String,
+}
+
+/// A single API operation on a path.
+#[derive(Serialize, Debug, Clone)]
+pub struct Operation {
+    /// The `operationId`, or one synthesized from method and path, e.g. `getPetsPetId`.
+    pub id: String,
+    /// The [`Operation::id`] in PascalCase, used as the prefix of the types hoisted from the operation.
+    pub name: String,
+    /// The lowercase HTTP method.
+    pub method: String,
+    /// The path the operation is available on.
+    pub path: String,
+    /// A short summary of what the operation does.
+    pub summary: Option<String>,
+    /// A verbose explanation of the operation behavior.
+    pub description: Option<String>,
+    /// A list of tags for API documentation control.
+    pub tags: Vec<String>,
+    /// Declares this operation to be deprecated.
+    pub deprecated: bool,
+    /// The parameters of the operation, including those declared on the path.
+    pub parameters: Vec<Parameter>,
+    /// The request body of the operation.
+    pub request_body: Option<Body>,
+    /// The responses of the operation, ordered by status.
+    pub responses: Vec<Response>,
+}
+
+/// A single operation parameter.
+#[derive(Serialize, Debug, Clone)]
+pub struct Parameter {
+    /// The name of the parameter.
+    pub name: String,
+    /// The location of the parameter, either `query`, `header`, `path` or `cookie`.
+    pub location: String,
+    /// Whether the parameter MUST be present.
+    pub required: bool,
+    /// A description of the parameter.
+    pub description: Option<String>,
+    /// The type of the parameter.
+    pub schema: Type,
+}
+
+/// The payload of a request or response.
+#[derive(Serialize, Debug, Clone)]
+pub struct Body {
+    /// The media type of the payload, preferring `application/json`.
+    pub content_type: String,
+    /// Whether the payload MUST be present.
+    pub required: bool,
+    /// A description of the payload.
+    pub description: Option<String>,
+    /// The type of the payload.
+    pub schema: Type,
+}
+
+/// A single response of an operation.
+#[derive(Serialize, Debug, Clone)]
+pub struct Response {
+    /// The HTTP status code, or `default`.
+    pub status: String,
+    /// A description of the response.
+    pub description: String,
+    /// The payload of the response, if any.
+    pub body: Option<Body>,
+}
+
+impl Ir {
+    pub fn build(document: &OpenAPI) -> anyhow::Result<Self> {
+        builder::Builder::new(document).build()
+    }
+}
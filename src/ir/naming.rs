@@ -0,0 +1,119 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::schema::SchemaObject;
+
+/// Hands out unique type names in the order they are requested.
+/// A name that is already taken gets the lowest free numeric suffix, e.g. `Pet2`.
+#[derive(Default)]
+pub struct Namer {
+    taken: HashSet<String>,
+    /// Names already handed out for a given origin, so a schema reached twice keeps its name.
+    assigned: HashMap<String, String>,
+}
+
+impl Namer {
+    /// Claims a unique name for the schema at `origin`, preferring `desired`.
+    pub fn claim(&mut self, origin: &str, desired: &str) -> String {
+        if let Some(name) = self.assigned.get(origin) {
+            return name.clone();
+        }
+
+        let mut name = desired.to_string();
+        let mut suffix = 2;
+        while self.taken.contains(&name) {
+            name = format!("{desired}{suffix}");
+            suffix += 1;
+        }
+
+        self.taken.insert(name.clone());
+        self.assigned.insert(origin.to_string(), name.clone());
+        name
+    }
+
+    /// Returns the name previously claimed for `origin`, if any.
+    pub fn get(&self, origin: &str) -> Option<&String> {
+        self.assigned.get(origin)
+    }
+}
+
+/// The name an inline schema asks for, falling back to the name synthesized from its position.
+/// `x-oam-name` takes precedence over `title`.
+pub fn preferred_name(schema: &SchemaObject, synthesized: &str) -> String {
+    if let Some(name) = &schema.oam_name {
+        return name.clone();
+    }
+    if let Some(title) = &schema.title {
+        return pascal_case(title);
+    }
+    synthesized.to_string()
+}
+
+/// Converts `createPets`, `/pets/{petId}` or `x-next` to `CreatePets`, `PetsPetId` and `XNext`.
+/// Unlike [case::pascal_case](crate::case::pascal_case), the rest of each word is kept as is,
+/// so type names keep their acronyms, e.g. `HTTPError`.
+pub fn pascal_case(value: &str) -> String {
+    value
+        .split(|character: char| !character.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut characters = word.chars();
+            match characters.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + characters.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suffixes_colliding_names() {
+        let mut namer = Namer::default();
+        assert_eq!(namer.claim("components.schemas.Pet", "Pet"), "Pet");
+        assert_eq!(namer.claim("paths./pets.get", "Pet"), "Pet2");
+        assert_eq!(namer.claim("paths./pets.post", "Pet"), "Pet3");
+        // A schema reached twice keeps its name.
+        assert_eq!(namer.claim("paths./pets.get", "Other"), "Pet2");
+        assert_eq!(
+            namer.get("paths./pets.post").map(String::as_str),
+            Some("Pet3")
+        );
+        assert_eq!(namer.get("paths./owners.get"), None);
+    }
+
+    #[test]
+    fn prefers_oam_name_over_title() {
+        let schema = |yaml: &str| -> SchemaObject { serde_yaml::from_str(yaml).unwrap() };
+
+        assert_eq!(
+            preferred_name(&schema("type: object"), "PetOwner"),
+            "PetOwner"
+        );
+        assert_eq!(
+            preferred_name(&schema("title: pet owner"), "PetOwner"),
+            "PetOwner"
+        );
+        assert_eq!(
+            preferred_name(&schema("title: the-keeper"), "PetOwner"),
+            "TheKeeper"
+        );
+        assert_eq!(
+            preferred_name(
+                &schema("{title: keeper, x-oam-name: Custodian}"),
+                "PetOwner"
+            ),
+            "Custodian"
+        );
+    }
+
+    #[test]
+    fn keeps_acronyms() {
+        assert_eq!(pascal_case("createPets"), "CreatePets");
+        assert_eq!(pascal_case("/pets/{petId}"), "PetsPetId");
+        assert_eq!(pascal_case("x-next"), "XNext");
+        assert_eq!(pascal_case("HTTPError"), "HTTPError");
+    }
+}
@@ -0,0 +1,224 @@
+use anyhow::anyhow;
+use rhai::{CallFnOptions, Dynamic, Engine, Scope, AST};
+use std::{collections::HashMap, fs::read_to_string, path::Path};
+
+use crate::case;
+
+/// A language script which maps IR types and names to the syntax of a target language.
+///
+/// Scripts for `rust`, `typescript`, `python`, `go` and `kotlin` are built in.
+/// A script under `.openapi/languages/{language}.rhai` replaces the built-in one of the same name.
+/// Scripts define the following functions:
+///
+/// - `map_type(schema)` returns the type expression of an IR type, which is passed as a map
+///   tagged by its `kind` (`primitive`, `array`, `map`, `nullable`, `ref`, ...). Required.
+/// - `escape_reserved(name)` escapes reserved words, e.g. `type` as `r#type`.
+/// - `type_name(name)` formats the name of a declared type.
+/// - `field_name(name)` formats the name of a field.
+///
+/// Optional functions default to returning the name unchanged.
+/// Scripts may call the naming helpers of the renderer, e.g. `snake_case(name)`,
+/// and read the options of the flavour with `option(name)`.
+pub struct Language {
+    pub name: String,
+    engine: Engine,
+    ast: AST,
+    script: String,
+}
+
+/// The language scripts shipped with the binary.
+pub const BUILTIN: [(&str, &str); 5] = [
+    ("rust", include_str!("languages/rust.rhai")),
+    ("typescript", include_str!("languages/typescript.rhai")),
+    ("python", include_str!("languages/python.rhai")),
+    ("go", include_str!("languages/go.rhai")),
+    ("kotlin", include_str!("languages/kotlin.rhai")),
+];
+
+impl Language {
+    /// Loads and compiles the script of the language `name`, preferring the project's own script.
+    pub fn load(name: &str) -> anyhow::Result<Self> {
+        let path = format!(".openapi/languages/{name}.rhai");
+        if Path::new(&path).exists() {
+            let script = read_to_string(&path)
+                .map_err(|error| anyhow!("Could not read language script {path}: {error}"))?;
+            return Language::compile(name, &script);
+        }
+
+        match BUILTIN.iter().find(|(builtin, _)| *builtin == name) {
+            Some((_, script)) => Language::compile(name, script),
+            None => Err(anyhow!(
+                "Unknown language {name}, add a script under {path} or use one of {}",
+                BUILTIN.map(|(builtin, _)| builtin).join(", ")
+            )),
+        }
+    }
+
+    pub fn compile(name: &str, script: &str) -> anyhow::Result<Self> {
+        let engine = engine();
+        let ast = engine
+            .compile(script)
+            .map_err(|error| anyhow!("Could not compile language script {name}: {error}"))?;
+
+        let defines_map_type = ast
+            .iter_functions()
+            .any(|function| function.name == "map_type" && function.params.len() == 1);
+        if !defines_map_type {
+            return Err(anyhow!(
+                "Language script {name} must define a function map_type(schema)"
+            ));
+        }
+
+        Ok(Language {
+            name: name.to_string(),
+            engine,
+            ast,
+            script: script.to_string(),
+        })
+    }
+
+    /// Makes the options of a flavour available to the script as `option(name)`,
+    /// which returns `()` for an option that is not declared.
+    pub fn with_options(
+        mut self,
+        options: &serde_json::Map<String, serde_json::Value>,
+    ) -> anyhow::Result<Self> {
+        let options = options
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), rhai::serde::to_dynamic(value)?)))
+            .collect::<Result<HashMap<String, Dynamic>, Box<rhai::EvalAltResult>>>()
+            .map_err(|error| {
+                anyhow!("Could not pass options to language {}: {error}", self.name)
+            })?;
+        self.engine.register_fn("option", move |name: &str| {
+            options.get(name).cloned().unwrap_or(Dynamic::UNIT)
+        });
+        Ok(self)
+    }
+
+    /// The source of the script.
+    pub fn script(&self) -> &str {
+        &self.script
+    }
+
+    /// Calls one of the functions of the script.
+    /// An optional function which is not defined returns its argument unchanged.
+    pub fn call(&self, function: &str, argument: Dynamic) -> anyhow::Result<String> {
+        let defined = self
+            .ast
+            .iter_functions()
+            .any(|defined| defined.name == function && defined.params.len() == 1);
+        if !defined && function != "map_type" {
+            return Ok(argument.to_string());
+        }
+
+        self.engine
+            .call_fn_with_options::<String>(
+                CallFnOptions::new().eval_ast(false),
+                &mut Scope::new(),
+                &self.ast,
+                function,
+                (argument,),
+            )
+            .map_err(|error| anyhow!("{function} of language {} failed: {error}", self.name))
+    }
+}
+
+/// An engine with the naming helpers registered.
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.register_fn("pascal_case", |value: &str| case::pascal_case(value));
+    engine.register_fn("camel_case", |value: &str| case::camel_case(value));
+    engine.register_fn("snake_case", |value: &str| case::snake_case(value));
+    engine.register_fn("screaming_snake_case", |value: &str| {
+        case::screaming_snake_case(value)
+    });
+    engine.register_fn("kebab_case", |value: &str| case::kebab_case(value));
+    engine.register_fn("pluralize", |value: &str| case::pluralize(value));
+    engine.register_fn("singularize", |value: &str| case::singularize(value));
+    engine.register_fn("sanitize_identifier", |value: &str| {
+        case::sanitize_identifier(value)
+    });
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Type;
+
+    /// `string`, `integer` with format `int32`, `boolean`, a list of strings and a nullable string.
+    fn basic_types() -> [Type; 5] {
+        let primitive = |r#type: &str, format: Option<&str>| Type::Primitive {
+            r#type: r#type.to_string(),
+            format: format.map(str::to_string),
+        };
+        [
+            primitive("string", None),
+            primitive("integer", Some("int32")),
+            primitive("boolean", None),
+            Type::Array {
+                items: Box::new(primitive("string", None)),
+            },
+            Type::Nullable {
+                inner: Box::new(primitive("string", None)),
+            },
+        ]
+    }
+
+    fn map(language: &Language) -> Vec<String> {
+        basic_types()
+            .iter()
+            .map(|shape| {
+                let argument = rhai::serde::to_dynamic(shape).unwrap();
+                language.call("map_type", argument).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn maps_basic_types() {
+        let expected = [
+            (
+                "rust",
+                ["String", "i32", "bool", "Vec<String>", "Option<String>"],
+            ),
+            (
+                "typescript",
+                [
+                    "string",
+                    "number",
+                    "boolean",
+                    "Array<string>",
+                    "string | null",
+                ],
+            ),
+            ("python", ["str", "int", "bool", "list[str]", "str | None"]),
+            ("go", ["string", "int32", "bool", "[]string", "*string"]),
+            (
+                "kotlin",
+                ["String", "Int", "Boolean", "List<String>", "String?"],
+            ),
+        ];
+        assert_eq!(expected.len(), BUILTIN.len());
+        for (name, script) in BUILTIN {
+            let language = Language::compile(name, script).unwrap();
+            let (_, types) = expected
+                .iter()
+                .find(|(language, _)| *language == name)
+                .unwrap();
+            assert_eq!(map(&language), types, "{name}");
+        }
+    }
+
+    #[test]
+    fn requires_map_type() {
+        let error = Language::compile("empty", "fn type_name(name) { name }")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Language script empty must define a function map_type(schema)"
+        );
+    }
+}
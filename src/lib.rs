@@ -1,6 +1,19 @@
+pub mod case;
 pub mod cli;
 pub mod commands;
 pub mod config;
+pub mod diff;
+pub mod fingerprint;
 pub mod flavour;
+pub mod ir;
+pub mod language;
+pub mod lint;
+pub mod locate;
+pub mod manifest;
+pub mod merge;
+pub mod options;
+pub mod plan;
 pub mod processor;
+pub mod renderer;
 pub mod schema;
+pub mod writer;
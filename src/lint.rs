@@ -0,0 +1,519 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::read_to_string,
+    path::Path,
+};
+use tera::Tera;
+
+use crate::{
+    fingerprint::Inputs,
+    flavour::{directory, Flavour, Template, WriteMode},
+    merge::mergeable,
+    renderer::{add_condition, condition_name, Renderer},
+};
+
+/// The documented shape of the context, as the keys known at a dotted path.
+/// `*` stands for any item of a map or list, so `ir.types.*` are the fields of an IR type.
+/// Paths which are not listed, such as `paths` or `components.schemas`, may have any keys.
+/// A test keeps the keys in line with the serialized document and [Ir](crate::ir::Ir).
+const SHAPE: [(&str, &[&str]); 6] = [
+    (
+        "",
+        &[
+            "openapi",
+            "info",
+            "paths",
+            "webhooks",
+            "components",
+            "security",
+            "tags",
+            "externalDocs",
+            "ir",
+            "options",
+            "language",
+        ],
+    ),
+    (
+        "info",
+        &[
+            "title",
+            "summary",
+            "description",
+            "termsOfService",
+            "contact",
+            "license",
+            "version",
+        ],
+    ),
+    (
+        "components",
+        &[
+            "schemas",
+            "responses",
+            "parameters",
+            "examples",
+            "requestBodies",
+            "headers",
+            "securitySchemes",
+            "links",
+            "callbacks",
+            "pathItems",
+        ],
+    ),
+    ("ir", &["types", "operations"]),
+    ("ir.types.*", &["name", "origin", "description", "schema"]),
+    (
+        "ir.operations.*",
+        &[
+            "id",
+            "name",
+            "method",
+            "path",
+            "summary",
+            "description",
+            "tags",
+            "deprecated",
+            "parameters",
+            "request_body",
+            "responses",
+        ],
+    ),
+];
+
+/// Variables Tera provides itself.
+const BUILTIN: [&str; 2] = ["loop", "__tera_context"];
+
+/// A mistake in a flavour, found without rendering it.
+pub struct Problem {
+    /// Where the mistake is, e.g. `.openapi/flavours/axum/model.tera:12`.
+    pub location: String,
+    pub message: String,
+}
+
+/// Checks a flavour without rendering it: that every template and asset exists, that templates parse,
+/// that the variables, filters and functions its templates and `when` expressions use exist, that
+/// output patterns only use the placeholders of their template, asset or fragment, and that fragments
+/// are written to files they can be merged into.
+///
+/// Variables are checked against the documented `SHAPE` of the context, the options the flavour
+/// declares and `entity` and `item` for iterated templates. Items of `ir.types` and `ir.operations`
+/// are checked field by field.
+pub fn lint(flavour: &Flavour) -> anyhow::Result<Vec<Problem>> {
+    let mut shape: BTreeMap<String, BTreeSet<String>> = SHAPE
+        .iter()
+        .map(|(path, keys)| {
+            (
+                path.to_string(),
+                keys.iter().map(|key| key.to_string()).collect(),
+            )
+        })
+        .collect();
+    shape.insert(
+        String::from("options"),
+        flavour.options.keys().cloned().collect(),
+    );
+    shape.insert(
+        String::from("language"),
+        BTreeSet::from([String::from("name")]),
+    );
+
+    let mut tera = Renderer::base(flavour, Default::default())?.tera().clone();
+    let mut problems = Vec::new();
+    for (index, template) in flavour.templates.iter().enumerate() {
+        let location = template.source.display().to_string();
+        let mut problem = |message: String| {
+            problems.push(Problem {
+                location: location.clone(),
+                message,
+            })
+        };
+
+        problems_of_output(template, &mut problem);
+        if let Some(iteration) = &template.iteration {
+            if let Some(message) = unknown(iteration, None, &shape) {
+                problem(format!("Iteration {iteration}: {message}"));
+            }
+        }
+
+        if !template.source.exists() {
+            problem(format!(
+                "Template {} not found in {}",
+                template.input,
+                flavour.chain.join(", ")
+            ));
+            continue;
+        }
+        if let Err(error) = tera.add_template_file(&template.source, Some(&template.input)) {
+            // A template which fails to resolve its parents stays registered and fails every later one.
+            tera.templates.remove(&template.input);
+            problem(chain(&error));
+            continue;
+        }
+        let source = read_to_string(&template.source)?;
+        for (identifier, message) in problems_of(&tera, &template.input, template, &shape) {
+            problems.push(Problem {
+                location: locate(&template.source, &source, &identifier),
+                message,
+            });
+        }
+
+        let Some(when) = &template.when else {
+            continue;
+        };
+        let location = format!("{location} when {when}");
+        match add_condition(&mut tera, index, when) {
+            Ok(()) => {
+                for (_, message) in problems_of(&tera, &condition_name(index), template, &shape) {
+                    problems.push(Problem {
+                        location: location.clone(),
+                        message,
+                    });
+                }
+            }
+            Err(error) => problems.push(Problem {
+                location,
+                message: chain(&error),
+            }),
+        }
+    }
+
+    for asset in &flavour.assets {
+        let location = asset.source.display().to_string();
+        if !asset.source.exists() {
+            problems.push(Problem {
+                location: location.clone(),
+                message: format!(
+                    "Asset {} not found in {}",
+                    asset.input,
+                    flavour.chain.join(", ")
+                ),
+            });
+        }
+        for message in problems_of_options(&asset.output, flavour) {
+            problems.push(Problem {
+                location: location.clone(),
+                message,
+            });
+        }
+    }
+    for fragment in &flavour.fragments {
+        let mut messages = problems_of_options(&fragment.output, flavour);
+        if !mergeable(Path::new(&fragment.output)) {
+            messages.push(format!(
+                "Fragment output {} is not a TOML, JSON or YAML file",
+                fragment.output
+            ));
+        }
+        for message in messages {
+            problems.push(Problem {
+                location: format!("{} fragment {}", config(flavour), fragment.output),
+                message,
+            });
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Checks the placeholders of an asset or fragment output, which are the options of the flavour.
+fn problems_of_options(output: &str, flavour: &Flavour) -> Vec<String> {
+    placeholders(output)
+        .filter(|placeholder| !flavour.options.contains_key(*placeholder))
+        .map(|placeholder| {
+            format!("Output {output} uses {{{placeholder}}}, which is not an option")
+        })
+        .collect()
+}
+
+/// The names of the placeholders of an output, e.g. `entity` for `src/{entity}.rs`.
+fn placeholders(output: &str) -> impl Iterator<Item = &str> {
+    output
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+}
+
+/// The configuration file of a flavour.
+fn config(flavour: &Flavour) -> String {
+    directory(&flavour.name)
+        .join("config.toml")
+        .display()
+        .to_string()
+}
+
+/// Checks the placeholders of an output pattern, of which iterated templates have `{entity}`.
+fn problems_of_output(template: &Template, problem: &mut impl FnMut(String)) {
+    let output = &template.output;
+    let mut entity = false;
+    for placeholder in placeholders(output) {
+        match (placeholder, &template.iteration) {
+            ("entity", Some(_)) => entity = true,
+            ("entity", None) => problem(format!(
+                "Output {output} uses {{entity}}, but template {} has no iteration",
+                template.input
+            )),
+            (placeholder, _) => problem(format!(
+                "Output {output} uses unknown placeholder {{{placeholder}}}"
+            )),
+        }
+    }
+    if template.iteration.is_some() && !entity {
+        problem(format!(
+            "Output {output} has no {{entity}}, so every item of template {} is written to the same file",
+            template.input
+        ));
+    }
+    if template.mode == WriteMode::Merge && !mergeable(Path::new(output)) {
+        problem(format!(
+            "Output {output} is merged into, but only TOML, JSON and YAML files can be"
+        ));
+    }
+}
+
+/// The unknown variables, filters and functions of the registered template `name`, and the
+/// identifiers they are about.
+fn problems_of(
+    tera: &Tera,
+    name: &str,
+    template: &Template,
+    shape: &BTreeMap<String, BTreeSet<String>>,
+) -> Vec<(String, String)> {
+    let inputs = Inputs::of(tera, name);
+    let mut problems = Vec::new();
+    for filter in &inputs.filters {
+        if tera.get_filter(filter).is_err() {
+            problems.push((filter.clone(), format!("Unknown filter `{filter}`")));
+        }
+    }
+    for function in &inputs.functions {
+        if tera.get_function(function).is_err() {
+            problems.push((function.clone(), format!("Unknown function `{function}`")));
+        }
+    }
+    for variable in &inputs.variables {
+        let root = variable.split('.').next().unwrap_or_default();
+        if BUILTIN.contains(&root) {
+            continue;
+        }
+        if let Some(message) = unknown(variable, template.iteration.as_deref(), shape) {
+            problems.push((variable.clone(), message));
+        }
+    }
+    problems
+}
+
+/// Why a variable is not part of the context, if it isn't.
+/// `entity` and `item` are part of it for templates iterating over `iteration`.
+fn unknown(
+    variable: &str,
+    iteration: Option<&str>,
+    shape: &BTreeMap<String, BTreeSet<String>>,
+) -> Option<String> {
+    let mut segments: Vec<&str> = variable.split('.').collect();
+    match (segments[0], iteration) {
+        ("entity", Some(_)) => return None,
+        // Reported for the iteration already.
+        ("item", Some(iteration)) if unknown(iteration, None, shape).is_some() => return None,
+        ("item", Some(iteration)) => {
+            segments.splice(0..1, iteration.split('.').chain(["*"]));
+        }
+        ("entity" | "item", None) => {
+            return Some(format!(
+                "Variable `{variable}` only exists in templates with an iteration"
+            ))
+        }
+        _ => {}
+    }
+
+    for position in 0..segments.len() {
+        let Some(keys) = shape.get(&segments[..position].join(".")) else {
+            continue;
+        };
+        if !keys.contains(segments[position]) {
+            return Some(format!("Unknown variable `{variable}`"));
+        }
+    }
+    None
+}
+
+/// The path of a template, with the first line mentioning `identifier` if any.
+fn locate(path: &Path, source: &str, identifier: &str) -> String {
+    match source.lines().position(|line| line.contains(identifier)) {
+        Some(line) => format!("{}:{}", path.display(), line + 1),
+        None => path.display().to_string(),
+    }
+}
+
+/// The messages of an error and its causes.
+fn chain(error: &tera::Error) -> String {
+    let mut messages = vec![error.to_string()];
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        messages.push(cause.to_string());
+        source = cause.source();
+    }
+    messages.join(": ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ir::Ir, schema::OpenAPI};
+    use std::path::PathBuf;
+
+    fn keys(value: &serde_json::Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    fn shape(path: &str) -> Vec<&'static str> {
+        let (_, keys) = SHAPE.iter().find(|(shape, _)| *shape == path).unwrap();
+        let mut keys = keys.to_vec();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn shape_matches_context() {
+        let document: OpenAPI = serde_yaml::from_str(
+            r#"
+openapi: 3.1.0
+info: { title: Pets, version: "1" }
+paths:
+  /pets:
+    get:
+      responses:
+        "200": { description: The pets }
+components:
+  schemas:
+    Pet: { type: object }
+"#,
+        )
+        .unwrap();
+        let ir = Ir::build(&document).unwrap();
+        let flavour = flavour(vec![template("lib", "lib.rs", None, None)]);
+        let contexts = Renderer::base(&flavour, Default::default())
+            .unwrap()
+            .contexts(&flavour, &document, &ir, None, None)
+            .unwrap();
+        let context = &contexts[0]["context"];
+        let ir = &context["ir"];
+
+        assert_eq!(keys(context), shape(""));
+        assert_eq!(keys(&context["info"]), shape("info"));
+        assert_eq!(keys(&context["components"]), shape("components"));
+        assert_eq!(keys(&context["language"]), ["name"]);
+
+        assert_eq!(keys(ir), shape("ir"));
+        assert_eq!(keys(&ir["types"]["Pet"]), shape("ir.types.*"));
+        assert_eq!(keys(&ir["operations"][0]), shape("ir.operations.*"));
+    }
+
+    fn flavour(templates: Vec<Template>) -> Flavour {
+        Flavour {
+            name: String::from("test"),
+            version: None,
+            language: String::from("rust"),
+            templates,
+            assets: Vec::new(),
+            fragments: Vec::new(),
+            options: BTreeMap::new(),
+            chain: vec![String::from("test")],
+        }
+    }
+
+    fn template(
+        input: &str,
+        output: &str,
+        iteration: Option<&str>,
+        when: Option<&str>,
+    ) -> Template {
+        Template {
+            input: input.to_string(),
+            output: output.to_string(),
+            iteration: iteration.map(str::to_string),
+            mode: WriteMode::Overwrite,
+            when: when.map(str::to_string),
+            source: PathBuf::from(input),
+        }
+    }
+
+    /// The messages of the problems of a flavour made of `template` with the given source, or none if
+    /// it doesn't exist.
+    fn problems(mut template: Template, source: Option<&str>) -> Vec<String> {
+        let directory = std::env::temp_dir().join(format!(
+            "oam-lint-{}-{}",
+            template.input,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        template.source = directory.join(format!("{}.tera", template.input));
+        if let Some(source) = source {
+            std::fs::write(&template.source, source).unwrap();
+        }
+
+        let problems = lint(&flavour(vec![template])).unwrap();
+        std::fs::remove_dir_all(directory).unwrap();
+        problems
+            .into_iter()
+            .map(|problem| problem.message)
+            .collect()
+    }
+
+    #[test]
+    fn accepts_known_variables_and_filters() {
+        let template = template("model", "{entity}.rs", Some("ir.types"), None);
+        let source = "struct {{ entity | pascal_case }} { {{ item.name }} {{ info.title }} }";
+        assert!(problems(template, Some(source)).is_empty());
+    }
+
+    #[test]
+    fn reports_unknown_filters() {
+        let template = template("model", "model.rs", None, None);
+        assert_eq!(
+            problems(template, Some("{{ info.title | pascal }}")),
+            ["Unknown filter `pascal`"]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_variables() {
+        let template = template("model", "model.rs", None, None);
+        assert_eq!(
+            problems(template, Some("{{ info.name }}")),
+            ["Unknown variable `info.name`"]
+        );
+    }
+
+    #[test]
+    fn reports_entity_without_iteration() {
+        let template = template("model", "{entity}.rs", None, None);
+        assert_eq!(
+            problems(template, Some("")),
+            ["Output {entity}.rs uses {entity}, but template model has no iteration"]
+        );
+    }
+
+    #[test]
+    fn reports_missing_templates() {
+        let template = template("model", "model.rs", None, None);
+        assert_eq!(
+            problems(template, None),
+            ["Template model not found in test"]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_when_expressions() {
+        let template = template("model", "model.rs", None, Some("options.serde and"));
+        let problems = problems(template, Some(""));
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("Failed to parse '@when/0'"));
+    }
+}
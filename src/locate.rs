@@ -0,0 +1,202 @@
+/// Finds the line of the value at a dotted path such as `components.schemas.Pet` or
+/// `paths./pets/{id}.get` in a YAML or pretty printed JSON document, by following indentation.
+/// Keys containing dots are matched against the keys of the document.
+/// Returns the line of the deepest key found, or `None` if not even the first one is.
+pub fn line(text: &str, path: &str) -> Option<usize> {
+    let lines = lines(text);
+
+    let mut range = 0..lines.len();
+    let mut remaining = path;
+    let mut found = None;
+    while !remaining.is_empty() && !range.is_empty() {
+        let level = lines[range.clone()].iter().map(|line| line.indent).min()?;
+        let children: Vec<usize> = range
+            .clone()
+            .filter(|&index| lines[index].indent == level)
+            .collect();
+
+        let (segment, rest) = remaining.split_once('.').unwrap_or((remaining, ""));
+        let chosen = match segment.parse::<usize>() {
+            // The nth item of a sequence, including the keys of the item on the following lines.
+            Ok(position) if children.iter().any(|&index| lines[index].item) => {
+                let items: Vec<usize> = children
+                    .iter()
+                    .copied()
+                    .filter(|&index| lines[index].item)
+                    .collect();
+                let Some(&start) = items.get(position) else {
+                    break;
+                };
+                let end = items.get(position + 1).copied().unwrap_or(range.end);
+                remaining = rest;
+                found = Some(lines[start].number);
+                range = start..end;
+                continue;
+            }
+            _ => children
+                .iter()
+                .copied()
+                .filter_map(|index| {
+                    let key = lines[index].key?;
+                    let rest = match remaining.strip_prefix(key)? {
+                        "" => "",
+                        rest => rest.strip_prefix('.')?,
+                    };
+                    Some((index, key.len(), rest))
+                })
+                .max_by_key(|(_, length, _)| *length),
+        };
+
+        let Some((index, _, rest)) = chosen else {
+            break;
+        };
+        let end = children
+            .iter()
+            .copied()
+            .find(|&child| child > index)
+            .unwrap_or(range.end);
+        found = Some(lines[index].number);
+        remaining = rest;
+        range = index + 1..end;
+    }
+
+    found
+}
+
+/// The lines of a YAML or pretty printed JSON document which hold a key or a sequence item.
+pub(crate) fn lines(text: &str) -> Vec<Line<'_>> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(index, line)| Line::parse(index + 1, line))
+        .collect()
+}
+
+/// A line of a document which holds a key or a sequence item.
+pub(crate) struct Line<'a> {
+    pub number: usize,
+    /// The whole line.
+    pub text: &'a str,
+    /// The column of the key, which for a sequence item is after its dash.
+    pub indent: usize,
+    /// The column of the first character, which for a sequence item is its dash.
+    pub start: usize,
+    pub key: Option<&'a str>,
+    /// What follows the key up to the end of the line, e.g. `object` for `type: object`, if anything
+    /// but a comment.
+    pub value: Option<&'a str>,
+    pub item: bool,
+}
+
+impl<'a> Line<'a> {
+    fn parse(number: usize, line: &'a str) -> Option<Self> {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            return None;
+        }
+
+        let start = line.len() - content.len();
+        let mut indent = start;
+        let item = content == "-" || content.starts_with("- ");
+        let content = match item {
+            true => {
+                let after = content[1..].trim_start();
+                indent += content.len() - after.len();
+                after
+            }
+            false => content,
+        };
+
+        let (key, value) = match content.split_once(": ") {
+            Some((key, value)) => (Some(key), Some(value.trim_start())),
+            None => (content.strip_suffix(':'), None),
+        };
+        let key = key.map(|key| key.trim().trim_matches(['"', '\'']));
+        let value = value.filter(|value| !value.trim_end().is_empty() && !value.starts_with('#'));
+        if key.is_none() && !item {
+            return None;
+        }
+
+        Some(Line {
+            number,
+            text: line,
+            indent,
+            start,
+            key,
+            value,
+            item,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YAML: &str = r#"openapi: 3.1.0
+paths:
+  /pets/{id}:
+    get:
+      parameters:
+        - name: id
+          in: path
+        - name: verbose
+          in: query
+      responses:
+        "200":
+          description: A pet
+components:
+  schemas:
+    # The pet itself.
+    Pet:
+      type: object
+    'pet.v2':
+      type: object
+"#;
+
+    #[test]
+    fn finds_nested_keys() {
+        assert_eq!(line(YAML, "components.schemas.Pet"), Some(16));
+        assert_eq!(line(YAML, "components.schemas.Pet.type"), Some(17));
+        // The deepest key found.
+        assert_eq!(line(YAML, "components.schemas.Cat"), Some(14));
+        assert_eq!(line(YAML, "webhooks"), None);
+    }
+
+    #[test]
+    fn finds_keys_containing_dots_and_slashes() {
+        assert_eq!(line(YAML, "paths./pets/{id}.get"), Some(4));
+        assert_eq!(line(YAML, "components.schemas.pet.v2.type"), Some(19));
+    }
+
+    #[test]
+    fn finds_quoted_keys() {
+        assert_eq!(line(YAML, "paths./pets/{id}.get.responses.200"), Some(11));
+        assert_eq!(
+            line(YAML, "paths./pets/{id}.get.responses.200.description"),
+            Some(12)
+        );
+    }
+
+    #[test]
+    fn finds_sequence_items() {
+        let parameters = "paths./pets/{id}.get.parameters";
+        assert_eq!(line(YAML, &format!("{parameters}.0")), Some(6));
+        assert_eq!(line(YAML, &format!("{parameters}.1")), Some(8));
+        assert_eq!(line(YAML, &format!("{parameters}.1.in")), Some(9));
+        assert_eq!(line(YAML, &format!("{parameters}.2")), Some(5));
+    }
+
+    #[test]
+    fn finds_keys_in_json() {
+        let json = r#"{
+  "components": {
+    "schemas": {
+      "Pet": {
+        "type": "object"
+      }
+    }
+  }
+}"#;
+        assert_eq!(line(json, "components.schemas.Pet.type"), Some(5));
+    }
+}
@@ -1,6 +1,6 @@
 use clap::Parser;
 use oam::{
-    cli::{Arguments, Command},
+    cli::{Arguments, Command, FlavourCommand},
     commands,
     config::AppConfig,
 };
@@ -9,10 +9,60 @@ fn main() -> anyhow::Result<()> {
     let arguments = Arguments::parse();
 
     match arguments.command {
-        Command::Init => commands::init(),
-        Command::Run { schema, flavour } => commands::run(AppConfig::new(schema, flavour)),
+        Command::Init { eject } => commands::init(eject),
+        Command::Run {
+            schema,
+            flavour,
+            options,
+            dry_run,
+            check,
+            report,
+            watch,
+            dump_context,
+            template,
+            entity,
+            format,
+            output,
+            force,
+            no_cache,
+        } => {
+            let mode = match (dry_run, check, dump_context) {
+                (_, _, true) => commands::Mode::DumpContext {
+                    template,
+                    entity,
+                    format,
+                    output,
+                },
+                (_, true, false) => commands::Mode::Check { report },
+                (true, false, false) => commands::Mode::DryRun,
+                (false, false, false) => commands::Mode::Write,
+            };
+            let config = AppConfig::load(schema, flavour, options)?;
+            match watch {
+                true => commands::watch(config, mode, force, no_cache),
+                false => commands::run(config, mode, force, no_cache),
+            }
+        }
         Command::Create { name } => commands::create(name),
         Command::Pull { name } => commands::pull(name),
+        Command::Render {
+            template,
+            schema,
+            each,
+            output,
+            language,
+        } => commands::render(
+            template,
+            AppConfig::load(schema, None, Vec::new())?,
+            each,
+            output,
+            language,
+        ),
+        Command::Flavour { command } => match command {
+            FlavourCommand::Show { name } => commands::flavour::show(name),
+            FlavourCommand::Test { name, bless } => commands::flavour::test(name, bless),
+            FlavourCommand::Check { name } => commands::flavour::check(name),
+        },
         Command::Push => todo!(),
     }?;
 
@@ -0,0 +1,150 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs::{read_to_string, write},
+    path::Path,
+};
+
+use crate::plan::{Change, ChangeKind};
+
+const PATH: &str = ".openapi/manifest.toml";
+
+/// The files generated by each flavour, recorded under `.openapi/manifest.toml` so that files
+/// a flavour no longer produces can be pruned.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Manifest {
+    #[serde(default)]
+    pub flavour: BTreeMap<String, Files>,
+}
+
+/// The generated files, keyed by their path relative to the project.
+pub type Files = BTreeMap<String, Entry>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "Recorded")]
+pub struct Entry {
+    /// The SHA-256 of the file as generated.
+    pub hash: String,
+    /// The [Fingerprint](crate::fingerprint::Fingerprint) of the inputs it was rendered from.
+    pub fingerprint: String,
+}
+
+/// An entry as found in a manifest, which before fingerprints were recorded was just the hash.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Recorded {
+    Entry { hash: String, fingerprint: String },
+    Hash(String),
+}
+
+impl From<Recorded> for Entry {
+    fn from(recorded: Recorded) -> Self {
+        match recorded {
+            Recorded::Entry { hash, fingerprint } => Entry { hash, fingerprint },
+            // No fingerprint matches an empty one, so the file is rendered again and then recorded in full.
+            Recorded::Hash(hash) => Entry {
+                hash,
+                fingerprint: String::new(),
+            },
+        }
+    }
+}
+
+impl Manifest {
+    /// Reads the manifest, which is empty before the first run.
+    pub fn load() -> anyhow::Result<Self> {
+        if !Path::new(PATH).exists() {
+            return Ok(Manifest::default());
+        }
+
+        let contents = read_to_string(PATH)?;
+        toml::from_str(&contents).map_err(|error| anyhow!("Invalid manifest {PATH}: {error}"))
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        write(PATH, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// The files last generated by the flavour `name`.
+    pub fn files(&self, name: &str) -> Files {
+        self.flavour.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Replaces the files of the flavour `name` with the outcome of applying `changes`.
+    /// Only files the flavour [owns](Change::owned) are recorded. Orphaned files keep their
+    /// recorded entry, so they are pruned once reverted or forced.
+    pub fn record(&mut self, name: &str, changes: &[Change]) {
+        let previous = self.files(name);
+        let files = changes
+            .iter()
+            .filter_map(|change| {
+                let path = change.path.display().to_string();
+                let entry = match change.kind {
+                    ChangeKind::Deleted => return None,
+                    ChangeKind::Orphaned => previous.get(&path)?.clone(),
+                    _ if !change.owned => return None,
+                    _ => Entry {
+                        hash: hash(change.after.as_ref()?),
+                        fingerprint: change.fingerprint.clone()?,
+                    },
+                };
+                Some((path, entry))
+            })
+            .collect();
+        self.flavour.insert(name.to_string(), files);
+    }
+}
+
+/// The hex encoded SHA-256 of a file.
+pub fn hash(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_entries() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+[flavour.axum."src/models.rs"]
+hash = "abc"
+fingerprint = "def"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.files("axum")["src/models.rs"],
+            Entry {
+                hash: String::from("abc"),
+                fingerprint: String::from("def"),
+            }
+        );
+    }
+
+    #[test]
+    fn migrates_hashes() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+[flavour.axum]
+"src/models.rs" = "abc"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.files("axum")["src/models.rs"],
+            Entry {
+                hash: String::from("abc"),
+                fingerprint: String::new(),
+            }
+        );
+        assert_eq!(
+            toml::to_string(&manifest).unwrap(),
+            "[flavour.axum.\"src/models.rs\"]\nhash = \"abc\"\nfingerprint = \"\"\n"
+        );
+    }
+}
@@ -0,0 +1,843 @@
+use anyhow::anyhow;
+use serde::Serialize;
+use serde_yaml::Value as Yaml;
+use std::{cmp::Reverse, collections::BTreeMap, ops::Range, path::Path};
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+use crate::locate::{lines, Line};
+
+/// Merges `rendered` into `existing` by structure, picking the format by the extension of `path`.
+/// Keys missing in `existing` are added and items missing from its lists are appended,
+/// but values already present in `existing` are never changed.
+///
+/// Files are edited rather than written again, so they keep their formatting and comments: TOML files
+/// through [toml_edit], JSON and YAML files by inserting what they miss into their text, see
+/// `merge_json` and `merge_yaml`.
+pub fn merge(path: &Path, existing: &str, rendered: &str) -> anyhow::Result<String> {
+    let error =
+        |error: &dyn std::fmt::Display| anyhow!("Could not merge into {}: {error}", path.display());
+
+    match extension(path) {
+        "json" => {
+            serde_json::from_str::<serde_json::Value>(existing).map_err(|e| error(&e))?;
+            // Read as YAML, which keeps the order of keys.
+            let rendered: serde_yaml::Value =
+                serde_yaml::from_str(rendered).map_err(|e| error(&e))?;
+            merge_json(existing, &rendered).map_err(|e| error(&e))
+        }
+        "yaml" | "yml" => {
+            let current: Yaml = serde_yaml::from_str(existing).map_err(|e| error(&e))?;
+            let rendered: Yaml = serde_yaml::from_str(rendered).map_err(|e| error(&e))?;
+            merge_yaml(existing, &current, &rendered).map_err(|e| error(&e))
+        }
+        "toml" => {
+            let mut merged: DocumentMut = existing.parse().map_err(|e| error(&e))?;
+            let rendered: DocumentMut = rendered.parse().map_err(|e| error(&e))?;
+            let end = merged.as_table().len();
+            merge_toml(merged.as_item_mut(), rendered.as_item(), None, end);
+
+            Ok(merged.to_string())
+        }
+        _ => Err(anyhow!(
+            "Could not merge into {}, only JSON, YAML and TOML files can be merged",
+            path.display()
+        )),
+    }
+}
+
+/// Whether files at `path` can be merged into, i.e. are JSON, YAML or TOML files.
+pub fn mergeable(path: &Path) -> bool {
+    matches!(extension(path), "json" | "yaml" | "yml" | "toml")
+}
+
+/// Writes a value in the format picked by the extension of `path`.
+pub fn serialize(path: &Path, value: &toml::Value) -> anyhow::Result<String> {
+    match extension(path) {
+        "json" => Ok(serde_json::to_string_pretty(value)? + "\n"),
+        "yaml" | "yml" => Ok(serde_yaml::to_string(value)?),
+        "toml" => Ok(toml::to_string(value)?),
+        _ => Err(anyhow!(
+            "Could not write {}, only JSON, YAML and TOML files can be merged",
+            path.display()
+        )),
+    }
+}
+
+/// Merges `rendered` into `existing` by structure, keeping the values of `existing`.
+pub fn merge_value(existing: &mut toml::Value, rendered: toml::Value) {
+    match (existing, rendered) {
+        (toml::Value::Table(existing), toml::Value::Table(rendered)) => {
+            for (key, value) in rendered {
+                match existing.get_mut(&key) {
+                    Some(current) => merge_value(current, value),
+                    None => {
+                        existing.insert(key, value);
+                    }
+                }
+            }
+        }
+        (toml::Value::Array(existing), toml::Value::Array(rendered)) => {
+            for item in rendered {
+                if !existing.contains(&item) {
+                    existing.push(item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn extension(path: &Path) -> &str {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+}
+
+/// Merges TOML items. `parent` is the position of the closest table `existing` is part of, after which
+/// tables added to it are placed. Tables added to the document itself are placed after the `end` of it.
+fn merge_toml(existing: &mut Item, rendered: &Item, parent: Option<usize>, end: usize) {
+    if let (Some(existing), Some(rendered)) =
+        (existing.as_table_like_mut(), rendered.as_table_like())
+    {
+        for (key, value) in rendered.iter() {
+            match existing.get_mut(key) {
+                Some(current) => {
+                    let parent = current.as_table().and_then(Table::position).or(parent);
+                    merge_toml(current, value, parent, end);
+                }
+                None => {
+                    let mut value = value.clone();
+                    place(&mut value, parent, end);
+                    existing.insert(key, value);
+                }
+            }
+        }
+        return;
+    }
+
+    match (existing, rendered) {
+        (Item::Value(Value::Array(existing)), Item::Value(Value::Array(rendered))) => {
+            for item in rendered {
+                let present = existing
+                    .iter()
+                    .any(|current| current.to_string().trim() == item.to_string().trim());
+                if !present {
+                    existing.push(item.clone());
+                }
+            }
+        }
+        (Item::ArrayOfTables(existing), Item::ArrayOfTables(rendered)) => {
+            for table in rendered {
+                let present = existing
+                    .iter()
+                    .any(|current| current.to_string().trim() == table.to_string().trim());
+                if !present {
+                    existing.push(table.clone());
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Formats an item added to a document, see [merge_toml]. Tables of plain values added to an existing
+/// table are written inline, the way dependencies usually are. Other tables are positioned after their
+/// parent, as tables with the same position are written in the order they are nested in, and set apart
+/// by a blank line.
+fn place(item: &mut Item, parent: Option<usize>, end: usize) {
+    if let (Item::Table(table), Some(_)) = (&mut *item, parent) {
+        if table.iter().all(|(_, value)| value.is_value()) {
+            let mut inline = table.clone().into_inline_table();
+            inline.fmt();
+            *item = Item::Value(Value::InlineTable(inline));
+            return;
+        }
+    }
+
+    let tables: Vec<&mut Table> = match item {
+        Item::Table(table) => vec![table],
+        Item::ArrayOfTables(array) => array.iter_mut().collect(),
+        _ => return,
+    };
+    for table in tables {
+        let position = table.position().unwrap_or_default();
+        table.set_position(parent.unwrap_or(end + position));
+        if table
+            .decor()
+            .prefix()
+            .and_then(|prefix| prefix.as_str())
+            .unwrap_or_default()
+            .is_empty()
+        {
+            table.decor_mut().set_prefix("\n");
+        }
+        for (mut key, child) in table.iter_mut() {
+            let nested = child.is_table();
+            place(child, parent, end);
+            // A table written inline is a value now, so its key is spaced like that of one.
+            if nested && child.is_value() {
+                key.fmt();
+            }
+        }
+    }
+}
+
+/// Merges JSON by inserting the members and items `existing` misses after its last ones, so the rest of
+/// its text is left as is. What is inserted follows the layout around it: a line per member, indented
+/// like its siblings, in objects and arrays spanning several lines, and a single line otherwise.
+fn merge_json(existing: &str, rendered: &serde_yaml::Value) -> anyhow::Result<String> {
+    let mut scanner = Scanner {
+        text: existing,
+        position: 0,
+    };
+    let node = scanner
+        .node()
+        .ok_or_else(|| anyhow!("invalid JSON at byte {}", scanner.position))?;
+    let mut insertions = Vec::new();
+    edit_json(
+        existing,
+        &node,
+        rendered,
+        &indentation(existing),
+        &mut insertions,
+    )?;
+
+    // From the end, so the positions of the remaining insertions stay valid.
+    insertions.sort_by_key(|(position, _)| Reverse(*position));
+    let mut merged = existing.to_string();
+    for (position, text) in insertions {
+        merged.insert_str(position, &text);
+    }
+    Ok(merged)
+}
+
+/// Collects the insertions needed to merge `rendered` into the JSON `node`, see [merge_json].
+fn edit_json(
+    text: &str,
+    node: &Node,
+    rendered: &serde_yaml::Value,
+    indentation: &str,
+    insertions: &mut Vec<(usize, String)>,
+) -> anyhow::Result<()> {
+    let mut added = Vec::new();
+    let last = match (&node.kind, rendered) {
+        (Kind::Object(members), serde_yaml::Value::Mapping(rendered)) => {
+            for (key, value) in rendered {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                match members.iter().find(|(name, _)| name == key) {
+                    Some((_, member)) => edit_json(text, member, value, indentation, insertions)?,
+                    None => added.push((Some(key), value)),
+                }
+            }
+            members.last().map(|(_, member)| member)
+        }
+        (Kind::Array(items), serde_yaml::Value::Sequence(rendered)) => {
+            let mut present: Vec<serde_yaml::Value> = items
+                .iter()
+                .filter_map(|item| serde_yaml::from_str(&text[item.start..item.end]).ok())
+                .collect();
+            for item in rendered {
+                if !present.contains(item) {
+                    present.push(item.clone());
+                    added.push((None, item));
+                }
+            }
+            items.last()
+        }
+        _ => return Ok(()),
+    };
+    if added.is_empty() {
+        return Ok(());
+    }
+
+    let outer = line_indentation(text, node.start);
+    let (position, multiline, inner) = match last {
+        Some(last) => (
+            last.end,
+            text[node.start..last.start].contains('\n'),
+            line_indentation(text, last.start).to_string(),
+        ),
+        None => (node.start + 1, true, format!("{outer}{indentation}")),
+    };
+    let mut entries = Vec::new();
+    for (key, value) in added {
+        let value = match multiline {
+            true => to_json(value, indentation)?.replace('\n', &format!("\n{inner}")),
+            false => serde_json::to_string(value)?,
+        };
+        entries.push(match key {
+            Some(key) => format!("{}: {value}", serde_json::Value::from(key)),
+            None => value,
+        });
+    }
+
+    let insertion = match (last, multiline) {
+        (None, _) => format!("\n{inner}{}\n{outer}", entries.join(&format!(",\n{inner}"))),
+        (Some(_), true) => entries
+            .iter()
+            .map(|entry| format!(",\n{inner}{entry}"))
+            .collect(),
+        (Some(_), false) => entries.iter().map(|entry| format!(", {entry}")).collect(),
+    };
+    insertions.push((position, insertion));
+    Ok(())
+}
+
+/// Merges YAML by inserting the keys and items `existing` misses after the last ones of their mapping or
+/// sequence, indented like them, so the rest of its text, comments included, is left as is. A collection
+/// written in flow style on a single line, e.g. `tags: [pets]`, is written again as a whole.
+/// `current` is the value of `existing`.
+fn merge_yaml(existing: &str, current: &Yaml, rendered: &Yaml) -> anyhow::Result<String> {
+    if current.is_null() {
+        let separator = match existing.is_empty() || existing.ends_with('\n') {
+            true => "",
+            false => "\n",
+        };
+        return Ok(format!(
+            "{existing}{separator}{}",
+            serde_yaml::to_string(rendered)?
+        ));
+    }
+
+    let document = Document {
+        text: existing.lines().collect(),
+        lines: lines(existing),
+    };
+    let mut edits = Edits::default();
+    document.edit(0..document.lines.len(), current, rendered, &mut edits)?;
+
+    let mut merged = String::with_capacity(existing.len());
+    for (index, line) in existing.split_inclusive('\n').enumerate() {
+        let number = index + 1;
+        merged.push_str(edits.replaced.get(&number).map_or(line, String::as_str));
+        if let Some(inserted) = edits.inserted.get(&number) {
+            if !merged.ends_with('\n') {
+                merged.push('\n');
+            }
+            merged.push_str(inserted);
+        }
+    }
+    Ok(merged)
+}
+
+/// The edits merging into a YAML document takes, by line number.
+#[derive(Default)]
+struct Edits {
+    /// Lines inserted after a line.
+    inserted: BTreeMap<usize, String>,
+    /// Lines written instead of a line.
+    replaced: BTreeMap<usize, String>,
+}
+
+/// A YAML document being merged into, see [merge_yaml].
+struct Document<'a> {
+    text: Vec<&'a str>,
+    /// The lines holding a key or a sequence item.
+    lines: Vec<Line<'a>>,
+}
+
+impl Document<'_> {
+    /// Collects the edits needed to merge `rendered` into the block collection on `lines[range]`, whose
+    /// value is `existing`.
+    fn edit(
+        &self,
+        range: Range<usize>,
+        existing: &Yaml,
+        rendered: &Yaml,
+        edits: &mut Edits,
+    ) -> anyhow::Result<()> {
+        let lines = &self.lines;
+        let Some(level) = lines[range.clone()].iter().map(|line| line.start).min() else {
+            return Ok(());
+        };
+
+        let mut added = Vec::new();
+        match (existing, rendered) {
+            (Yaml::Mapping(existing), Yaml::Mapping(rendered)) => {
+                // Sequences may be indented like the key they belong to, so their items are left out.
+                let keys: Vec<usize> = range
+                    .clone()
+                    .filter(|&index| {
+                        let line = &lines[index];
+                        line.start == level && !line.item && line.key.is_some()
+                    })
+                    .collect();
+                for (key, value) in rendered {
+                    let Some(current) = existing.get(key) else {
+                        added.push(serde_yaml::to_string(&serde_yaml::Mapping::from_iter([(
+                            key.clone(),
+                            value.clone(),
+                        )]))?);
+                        continue;
+                    };
+                    let text = key_text(key);
+                    let Some(position) = keys
+                        .iter()
+                        .position(|&index| text.is_some() && lines[index].key == text.as_deref())
+                    else {
+                        return Err(anyhow!(
+                            "key {} is not written in block style",
+                            text.unwrap_or_default()
+                        ));
+                    };
+                    let end = keys.get(position + 1).copied().unwrap_or(range.end);
+                    self.edit_entry(keys[position], end, current, value, edits)?;
+                }
+            }
+            (Yaml::Sequence(existing), Yaml::Sequence(rendered)) => {
+                let mut present = existing.clone();
+                for item in rendered {
+                    if !present.contains(item) {
+                        present.push(item.clone());
+                        let text = serde_yaml::to_string(item)?;
+                        added.push(format!("- {}", text.replace('\n', "\n  ").trim_end()) + "\n");
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if !added.is_empty() {
+            let indentation = " ".repeat(level);
+            let inserted = edits
+                .inserted
+                .entry(self.last_line(range, level))
+                .or_default();
+            for line in added.iter().flat_map(|text| text.lines()) {
+                inserted.push_str(&format!("{indentation}{line}\n"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the edits needed to merge `rendered` into the value of the key on `lines[key]`, whose
+    /// block ends before `lines[end]`.
+    fn edit_entry(
+        &self,
+        key: usize,
+        end: usize,
+        existing: &Yaml,
+        rendered: &Yaml,
+        edits: &mut Edits,
+    ) -> anyhow::Result<()> {
+        let collections = matches!(
+            (existing, rendered),
+            (Yaml::Mapping(_), Yaml::Mapping(_)) | (Yaml::Sequence(_), Yaml::Sequence(_))
+        );
+        if !collections {
+            return Ok(());
+        }
+        if key + 1 < end {
+            return self.edit(key + 1..end, existing, rendered, edits);
+        }
+
+        // A collection in flow style, written again with what it misses.
+        let mut merged = existing.clone();
+        add_yaml(&mut merged, rendered.clone());
+        if merged == *existing {
+            return Ok(());
+        }
+        let line = &self.lines[key];
+        let flow = line
+            .value
+            .filter(|value| value.starts_with(['{', '[']))
+            .zip(line.value.and_then(|value| value.rfind(['}', ']'])));
+        let Some((value, close)) = flow else {
+            return Err(anyhow!(
+                "the value of {} on line {} is neither a block nor a single line in flow style",
+                line.key.unwrap_or_default(),
+                line.number
+            ));
+        };
+        let start = line.text.len() - value.len();
+        edits.replaced.insert(
+            line.number,
+            format!(
+                "{}{}{}\n",
+                &line.text[..start],
+                serde_json::to_string(&merged)?,
+                &value[close + 1..]
+            ),
+        );
+        Ok(())
+    }
+
+    /// The number of the last line of the block on `lines[range]`, whose entries are indented by `level`.
+    /// Those are the lines up to the next entry, including block scalars, without blank lines and the
+    /// comments of the next entry, which are indented less.
+    fn last_line(&self, range: Range<usize>, level: usize) -> usize {
+        let first = self.lines[range.start].number;
+        let next = self
+            .lines
+            .get(range.end)
+            .map_or(self.text.len() + 1, |line| line.number);
+        (first..next)
+            .rev()
+            .find(|&number| {
+                let line = self.text[number - 1];
+                let content = line.trim_start();
+                !content.is_empty()
+                    && (!content.starts_with('#') || line.len() - content.len() >= level)
+            })
+            .unwrap_or(first)
+    }
+}
+
+/// Adds the keys and items of `rendered` missing in `existing`, keeping the values of `existing`.
+fn add_yaml(existing: &mut Yaml, rendered: Yaml) {
+    match (existing, rendered) {
+        (Yaml::Mapping(existing), Yaml::Mapping(rendered)) => {
+            for (key, value) in rendered {
+                match existing.get_mut(&key) {
+                    Some(current) => add_yaml(current, value),
+                    None => {
+                        existing.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Yaml::Sequence(existing), Yaml::Sequence(rendered)) => {
+            for item in rendered {
+                if !existing.contains(&item) {
+                    existing.push(item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// How a scalar key is written, e.g. `200` for the number 200.
+fn key_text(key: &Yaml) -> Option<String> {
+    match key {
+        Yaml::String(key) => Some(key.clone()),
+        Yaml::Number(key) => Some(key.to_string()),
+        Yaml::Bool(key) => Some(key.to_string()),
+        _ => None,
+    }
+}
+
+/// A JSON value, located in the text it was read from.
+struct Node {
+    /// The position of its first character.
+    start: usize,
+    /// The position after its last character.
+    end: usize,
+    kind: Kind,
+}
+
+enum Kind {
+    Object(Vec<(String, Node)>),
+    Array(Vec<Node>),
+    Scalar,
+}
+
+/// Reads the structure of a JSON document with the positions of its values, see [Node].
+struct Scanner<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Scanner<'_> {
+    fn node(&mut self) -> Option<Node> {
+        self.whitespace();
+        let start = self.position;
+        let kind = match self.peek()? {
+            b'{' => {
+                let mut members = Vec::new();
+                self.position += 1;
+                while self.next_entry(b'}', members.is_empty())? {
+                    self.whitespace();
+                    let key = self.string()?;
+                    let key = serde_json::from_str(&self.text[key..self.position]).ok()?;
+                    self.whitespace();
+                    self.expect(b':')?;
+                    members.push((key, self.node()?));
+                }
+                Kind::Object(members)
+            }
+            b'[' => {
+                let mut items = Vec::new();
+                self.position += 1;
+                while self.next_entry(b']', items.is_empty())? {
+                    items.push(self.node()?);
+                }
+                Kind::Array(items)
+            }
+            b'"' => {
+                self.string()?;
+                Kind::Scalar
+            }
+            _ => {
+                let length = self.text[start..]
+                    .find(|character: char| ",:]} \t\r\n".contains(character))
+                    .unwrap_or(self.text.len() - start);
+                self.position += length;
+                Kind::Scalar
+            }
+        };
+        Some(Node {
+            start,
+            end: self.position,
+            kind,
+        })
+    }
+
+    /// Moves past the comma before the next entry of an object or array, or past its `close` if there is
+    /// no next entry, returning whether there is one.
+    fn next_entry(&mut self, close: u8, first: bool) -> Option<bool> {
+        self.whitespace();
+        if self.peek()? == close {
+            self.position += 1;
+            return Some(false);
+        }
+        if !first {
+            self.expect(b',')?;
+        }
+        Some(true)
+    }
+
+    /// Moves past a string, returning the position it starts at.
+    fn string(&mut self) -> Option<usize> {
+        let start = self.position;
+        self.expect(b'"')?;
+        let mut escaped = false;
+        for (offset, character) in self.text[self.position..].char_indices() {
+            match character {
+                '\\' if !escaped => escaped = true,
+                '"' if !escaped => {
+                    self.position += offset + 1;
+                    return Some(start);
+                }
+                _ => escaped = false,
+            }
+        }
+        None
+    }
+
+    fn expect(&mut self, character: u8) -> Option<()> {
+        (self.peek()? == character).then(|| self.position += 1)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.position).copied()
+    }
+
+    fn whitespace(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start_matches([' ', '\t', '\r', '\n']).len();
+    }
+}
+
+/// The indentation of the line of `text` containing `position`.
+fn line_indentation(text: &str, position: usize) -> &str {
+    let line = &text[text[..position].rfind('\n').map_or(0, |start| start + 1)..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// The indentation of a JSON document, taken from its first indented line. Two spaces by default.
+fn indentation(json: &str) -> String {
+    json.lines()
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .find(|indentation| !indentation.is_empty())
+        .unwrap_or("  ")
+        .to_string()
+}
+
+fn to_json(value: &serde_yaml::Value, indentation: &str) -> anyhow::Result<String> {
+    let mut json = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indentation.as_bytes());
+    value.serialize(&mut serde_json::Serializer::with_formatter(
+        &mut json, formatter,
+    ))?;
+    Ok(String::from_utf8(json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_values_keeping_existing_ones() {
+        let mut existing: toml::Value = toml::from_str(
+            "name = \"pets\"\nfeatures = [\"json\"]\n[dependencies]\nserde = \"1\"\n",
+        )
+        .unwrap();
+        let rendered: toml::Value = toml::from_str(
+            "name = \"other\"\nfeatures = [\"json\", \"yaml\"]\n[dependencies]\nserde = \"2\"\ntokio = \"1\"\n",
+        )
+        .unwrap();
+        merge_value(&mut existing, rendered);
+        assert_eq!(
+            existing,
+            toml::from_str(
+                "name = \"pets\"\nfeatures = [\"json\", \"yaml\"]\n[dependencies]\nserde = \"1\"\ntokio = \"1\"\n",
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn merges_json_keeping_its_formatting() {
+        let existing = r#"{
+    "name": "pets",
+    "keywords": ["api"],
+    "scripts": {},
+    "dependencies": {
+        "axios": "^1.0.0"
+    },
+    "files": [
+        "dist"
+    ]
+}
+"#;
+        let rendered = r#"{
+  "name": "other",
+  "version": "1.0.0",
+  "keywords": ["api", "openapi"],
+  "scripts": { "build": "tsc" },
+  "dependencies": { "axios": "^0.1.0", "zod": "^3.0.0" },
+  "files": ["dist", "types"]
+}"#;
+        let merged = merge(Path::new("package.json"), existing, rendered).unwrap();
+        assert_eq!(
+            merged,
+            r#"{
+    "name": "pets",
+    "keywords": ["api", "openapi"],
+    "scripts": {
+        "build": "tsc"
+    },
+    "dependencies": {
+        "axios": "^1.0.0",
+        "zod": "^3.0.0"
+    },
+    "files": [
+        "dist",
+        "types"
+    ],
+    "version": "1.0.0"
+}
+"#
+        );
+        assert_eq!(
+            merge(Path::new("package.json"), &merged, rendered).unwrap(),
+            merged
+        );
+    }
+
+    #[test]
+    fn merges_json_on_a_single_line() {
+        let path = Path::new("config.json");
+        assert_eq!(
+            merge(
+                path,
+                "{\"a\": \"}\\\"]\", \"b\": [1]}\n",
+                "{\"b\": [1, 2], \"c\": {\"d\": true}}"
+            )
+            .unwrap(),
+            "{\"a\": \"}\\\"]\", \"b\": [1, 2], \"c\": {\"d\":true}}\n"
+        );
+        assert_eq!(merge(path, "{}", "{\"a\": 1}").unwrap(), "{\n  \"a\": 1\n}");
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        let error = merge(Path::new("package.json"), "{ \"name\": ", "{}").unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Could not merge into package.json"));
+    }
+
+    #[test]
+    fn merges_yaml_keeping_its_comments() {
+        let existing = r#"# Pets service
+name: pets # the name
+tags: [pets] # shown in the catalog
+dependencies:
+  # HTTP
+  axios: ^1.0.0
+files:
+- dist
+scripts:
+  build: |
+    tsc
+    cp a b
+
+# Deployment
+deploy:
+  replicas: 2
+"#;
+        let rendered = r#"
+name: other
+version: 1.0.0
+tags: [pets, dogs]
+dependencies:
+  axios: ^0.1.0
+  zod: ^3.0.0
+files: [dist, types]
+scripts:
+  test: jest
+deploy:
+  replicas: 3
+  ports:
+  - port: 80
+    name: http
+"#;
+        let merged = merge(Path::new("service.yaml"), existing, rendered).unwrap();
+        assert_eq!(
+            merged,
+            r#"# Pets service
+name: pets # the name
+tags: ["pets","dogs"] # shown in the catalog
+dependencies:
+  # HTTP
+  axios: ^1.0.0
+  zod: ^3.0.0
+files:
+- dist
+- types
+scripts:
+  build: |
+    tsc
+    cp a b
+  test: jest
+
+# Deployment
+deploy:
+  replicas: 2
+  ports:
+  - port: 80
+    name: http
+version: 1.0.0
+"#
+        );
+        assert_eq!(
+            merge(Path::new("service.yaml"), &merged, rendered).unwrap(),
+            merged
+        );
+    }
+
+    #[test]
+    fn merges_yaml_into_empty_files() {
+        assert_eq!(
+            merge(Path::new("config.yml"), "# Settings", "a: 1").unwrap(),
+            "# Settings\na: 1\n"
+        );
+        let error = merge(Path::new("config.yml"), "a: [1,\n  2]\n", "a: [3]").unwrap_err();
+        assert!(error.to_string().contains("flow style"));
+    }
+
+    #[test]
+    fn places_added_toml_tables() {
+        let existing = "[package]\nname = \"pets\"\n\n[dependencies]\nserde = \"1\"\n\n[features]\ndefault = []\n";
+        let rendered = "[dependencies]\ntokio = { version = \"1\" }\n\n[dependencies.axum]\nversion = \"0.7\"\nfeatures = [\"json\"]\n\n[dependencies.sqlx.features]\npostgres = true\n\n[lib]\npath = \"src/lib.rs\"\n";
+        let merged = merge(Path::new("Cargo.toml"), existing, rendered).unwrap();
+        assert_eq!(
+            merged,
+            "[package]\nname = \"pets\"\n\n[dependencies]\nserde = \"1\"\ntokio = { version = \"1\" }\naxum = { version = \"0.7\", features = [\"json\"] }\n\n[dependencies.sqlx]\nfeatures = { postgres = true }\n\n[features]\ndefault = []\n\n[lib]\npath = \"src/lib.rs\"\n"
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::anyhow;
+use serde_json::{Map, Value};
+
+use crate::{
+    config::AppConfig,
+    flavour::{Flavour, FlavourOption, OptionType},
+};
+
+/// Resolves the values of the options of a flavour: `-o key=value` first, then the project's
+/// `[options.<flavour>]`, then the defaults of the flavour.
+/// Fails on unknown options, values of the wrong type and options without a value.
+pub fn resolve(flavour: &Flavour, config: &AppConfig) -> anyhow::Result<Map<String, Value>> {
+    let project = config
+        .options
+        .get(&flavour.name)
+        .cloned()
+        .unwrap_or_default();
+    for key in project
+        .keys()
+        .chain(config.overrides.iter().map(|(key, _)| key))
+    {
+        if !flavour.options.contains_key(key) {
+            return Err(anyhow!(
+                "Unknown option {key} of flavour {}, expected one of {}",
+                flavour.name,
+                flavour
+                    .options
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    let mut values = Map::new();
+    for (name, option) in &flavour.options {
+        let overridden = config
+            .overrides
+            .iter()
+            .rev()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| parse(option, value))
+            .transpose()
+            .map_err(|error| anyhow!("Invalid value of option {name}: {error}"))?;
+
+        let value = match overridden.or_else(|| project.get(name).cloned()) {
+            Some(value) => value,
+            None => option.default.clone().ok_or_else(|| {
+                anyhow!(
+                    "Option {name} of flavour {} has no default and must be set in .openapi/config.toml or with -o {name}=<value>",
+                    flavour.name
+                )
+            })?,
+        };
+        check(option, &value)
+            .map_err(|error| anyhow!("Invalid value of option {name}: {error}"))?;
+        values.insert(name.clone(), serde_json::to_value(value)?);
+    }
+
+    Ok(values)
+}
+
+/// Parses a value given on the command line as the type of the option.
+fn parse(option: &FlavourOption, value: &str) -> anyhow::Result<toml::Value> {
+    Ok(match option.r#type {
+        OptionType::Bool => toml::Value::Boolean(value.parse()?),
+        OptionType::String => toml::Value::String(value.to_string()),
+        OptionType::Integer => toml::Value::Integer(value.parse()?),
+        OptionType::Float => toml::Value::Float(value.parse()?),
+    })
+}
+
+fn check(option: &FlavourOption, value: &toml::Value) -> anyhow::Result<()> {
+    let matches = matches!(
+        (option.r#type, value),
+        (OptionType::Bool, toml::Value::Boolean(_))
+            | (OptionType::String, toml::Value::String(_))
+            | (OptionType::Integer, toml::Value::Integer(_))
+            | (
+                OptionType::Float,
+                toml::Value::Float(_) | toml::Value::Integer(_)
+            )
+    );
+    if !matches {
+        return Err(anyhow!("expected a {} but got {value}", option.r#type));
+    }
+
+    match (&option.values, value.as_str()) {
+        (Some(values), Some(value)) if !values.iter().any(|allowed| allowed == value) => Err(
+            anyhow!("expected one of {} but got {value}", values.join(", ")),
+        ),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn flavour() -> Flavour {
+        let option = |r#type, default: toml::Value, values: Option<&[&str]>| FlavourOption {
+            r#type,
+            default: Some(default),
+            description: None,
+            values: values.map(|values| values.iter().map(|value| value.to_string()).collect()),
+        };
+        Flavour {
+            name: String::from("demo"),
+            version: None,
+            language: String::from("rust"),
+            templates: Vec::new(),
+            assets: Vec::new(),
+            fragments: Vec::new(),
+            options: BTreeMap::from([
+                (
+                    String::from("serde"),
+                    option(OptionType::Bool, toml::Value::Boolean(false), None),
+                ),
+                (
+                    String::from("case"),
+                    option(
+                        OptionType::String,
+                        toml::Value::String(String::from("snake")),
+                        Some(&["snake", "camel"]),
+                    ),
+                ),
+            ]),
+            chain: Vec::new(),
+        }
+    }
+
+    /// A configuration with `project` as `[options.demo]` and `overrides` as `-o key=value`.
+    fn config(project: &str, overrides: &[(&str, &str)]) -> AppConfig {
+        let mut config = AppConfig::new(None, Some(String::from("demo")));
+        config
+            .options
+            .insert(String::from("demo"), toml::from_str(project).unwrap());
+        config.overrides = overrides
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        config
+    }
+
+    fn error(project: &str, overrides: &[(&str, &str)]) -> String {
+        resolve(&flavour(), &config(project, overrides))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn overrides_defaults_with_the_project_and_the_command_line() {
+        let resolve = |project, overrides| {
+            let values = resolve(&flavour(), &config(project, overrides)).unwrap();
+            (values["serde"].clone(), values["case"].clone())
+        };
+        assert_eq!(resolve("", &[]), (Value::from(false), Value::from("snake")));
+        assert_eq!(
+            resolve("serde = true\ncase = 'camel'", &[]),
+            (Value::from(true), Value::from("camel"))
+        );
+        assert_eq!(
+            resolve("serde = true", &[("serde", "false"), ("serde", "true")]),
+            (Value::from(true), Value::from("snake"))
+        );
+        assert_eq!(
+            resolve("case = 'camel'", &[("case", "snake")]),
+            (Value::from(false), Value::from("snake"))
+        );
+    }
+
+    #[test]
+    fn rejects_values_of_the_wrong_type() {
+        assert!(error("", &[("serde", "maybe")]).starts_with("Invalid value of option serde"));
+        assert_eq!(
+            error("serde = 'yes'", &[]),
+            "Invalid value of option serde: expected a bool but got \"yes\""
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        assert_eq!(
+            error("", &[("serd", "true")]),
+            "Unknown option serd of flavour demo, expected one of case, serde"
+        );
+        assert!(error("derive = true", &[]).starts_with("Unknown option derive"));
+    }
+
+    #[test]
+    fn rejects_values_outside_the_choices() {
+        assert_eq!(
+            error("", &[("case", "kebab")]),
+            "Invalid value of option case: expected one of snake, camel but got kebab"
+        );
+        assert!(error("case = 'kebab'", &[]).starts_with("Invalid value of option case"));
+    }
+}
@@ -0,0 +1,349 @@
+use serde::Serialize;
+use std::{
+    fs::{create_dir_all, read, remove_file, write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    flavour::WriteMode,
+    manifest::{hash, Files},
+    renderer::RenderedFile,
+    writer::output,
+};
+
+/// What regenerating does to a single file.
+#[derive(Debug)]
+pub struct Change {
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+    /// The contents on disk, if the file exists.
+    pub before: Option<Vec<u8>>,
+    /// The contents after regenerating, if the file still exists.
+    pub after: Option<Vec<u8>>,
+    /// The fingerprint of the inputs of a rendered file.
+    pub fingerprint: Option<String>,
+    /// The permission bits the file is given, if any.
+    pub permissions: Option<u32>,
+    /// Whether the file belongs to the flavour, i.e. is overwritten and was created by a run.
+    /// Only such files are recorded in the manifest and deleted once no longer generated.
+    pub owned: bool,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+    Unchanged,
+    /// No longer produced by the flavour, but kept because it was modified since generation.
+    Orphaned,
+}
+
+/// The number of files per kind of change.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub created: usize,
+    pub modified: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+    pub orphaned: usize,
+}
+
+/// Compares rendered files to the files on disk, without writing anything.
+///
+/// Files in `generated` which are no longer rendered are deleted, unless they were modified
+/// since they were generated. `force` deletes those as well. Files which existed before the flavour
+/// first wrote them, and those appended to, merged into or created once, belong to the project and
+/// are never recorded, so never deleted.
+pub fn plan(files: &[RenderedFile], generated: &Files, force: bool) -> anyhow::Result<Vec<Change>> {
+    let mut changes = files
+        .iter()
+        .map(|file| {
+            // Permissions changed by hand are restored even if the contents are as rendered.
+            if file.cached {
+                let kind = match has_permissions(&file.path, file.permissions) {
+                    true => ChangeKind::Unchanged,
+                    false => ChangeKind::Modified,
+                };
+                return Ok(Change {
+                    path: file.path.clone(),
+                    kind,
+                    before: Some(file.contents.clone()),
+                    after: Some(file.contents.clone()),
+                    fingerprint: Some(file.fingerprint.clone()),
+                    permissions: file.permissions,
+                    owned: file.mode == WriteMode::Overwrite,
+                    warnings: Vec::new(),
+                });
+            }
+
+            let before = read(&file.path).ok();
+            let output = output(file, before.as_deref())?;
+            let after = output.contents.or_else(|| before.clone());
+
+            let owned = file.mode == WriteMode::Overwrite
+                && (before.is_none() || generated.contains_key(&file.path.display().to_string()));
+            let kind = match (&before, &after) {
+                (None, _) => ChangeKind::Created,
+                (Some(before), Some(after))
+                    if before == after && has_permissions(&file.path, file.permissions) =>
+                {
+                    ChangeKind::Unchanged
+                }
+                _ => ChangeKind::Modified,
+            };
+
+            Ok(Change {
+                path: file.path.clone(),
+                kind,
+                before,
+                after,
+                fingerprint: Some(file.fingerprint.clone()),
+                permissions: file.permissions,
+                owned,
+                warnings: output.warnings,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    for (path, recorded) in generated {
+        let path = PathBuf::from(path);
+        if files.iter().any(|file| file.path == path) {
+            continue;
+        }
+        // Files removed by hand are simply forgotten.
+        let Ok(before) = read(&path) else {
+            continue;
+        };
+
+        let change = match force || hash(&before) == recorded.hash {
+            true => Change {
+                path,
+                kind: ChangeKind::Deleted,
+                before: Some(before),
+                after: None,
+                fingerprint: None,
+                permissions: None,
+                owned: false,
+                warnings: Vec::new(),
+            },
+            false => Change {
+                warnings: vec![format!(
+                    "{} is no longer generated but was modified since, use --force to delete it",
+                    path.display()
+                )],
+                path,
+                kind: ChangeKind::Orphaned,
+                after: Some(before.clone()),
+                before: Some(before),
+                fingerprint: None,
+                permissions: None,
+                owned: false,
+            },
+        };
+        changes.push(change);
+    }
+
+    Ok(changes)
+}
+
+impl Change {
+    /// Whether the file on disk differs from what the flavour generates.
+    pub fn is_drift(&self) -> bool {
+        self.kind != ChangeKind::Unchanged
+    }
+
+    /// Writes or deletes the file, creating its directory if needed.
+    pub fn apply(&self) -> anyhow::Result<()> {
+        match (self.kind, &self.after) {
+            (ChangeKind::Created | ChangeKind::Modified, Some(after)) => {
+                if let Some(parent) = self.path.parent() {
+                    create_dir_all(parent)?;
+                }
+                write(&self.path, after)?;
+                if let Some(permissions) = self.permissions {
+                    set_permissions(&self.path, permissions)?;
+                }
+            }
+            (ChangeKind::Deleted, _) => remove_file(&self.path)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Whether a file has the permission bits the flavour gives it, if any. Always the case off Unix.
+#[cfg(unix)]
+fn has_permissions(path: &Path, permissions: Option<u32>) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    permissions.is_none_or(|permissions| {
+        std::fs::metadata(path)
+            .is_ok_and(|metadata| metadata.permissions().mode() & 0o7777 == permissions)
+    })
+}
+
+#[cfg(not(unix))]
+fn has_permissions(_path: &Path, _permissions: Option<u32>) -> bool {
+    true
+}
+
+#[cfg(unix)]
+fn set_permissions(path: &Path, permissions: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(permissions))
+}
+
+#[cfg(not(unix))]
+fn set_permissions(_path: &Path, _permissions: u32) -> std::io::Result<()> {
+    Ok(())
+}
+
+impl Summary {
+    pub fn of(changes: &[Change]) -> Self {
+        let mut summary = Summary::default();
+        for change in changes {
+            match change.kind {
+                ChangeKind::Created => summary.created += 1,
+                ChangeKind::Modified => summary.modified += 1,
+                ChangeKind::Deleted => summary.deleted += 1,
+                ChangeKind::Unchanged => summary.unchanged += 1,
+                ChangeKind::Orphaned => summary.orphaned += 1,
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::Manifest;
+    use std::fs::read_to_string;
+
+    fn rendered(path: &Path, contents: impl Into<Vec<u8>>, mode: WriteMode) -> RenderedFile {
+        RenderedFile {
+            path: path.to_path_buf(),
+            contents: contents.into(),
+            mode,
+            fingerprint: String::from("fingerprint"),
+            cached: false,
+            permissions: None,
+        }
+    }
+
+    /// Applies the plan for `files` and records it, as `oam run` does.
+    fn run(manifest: &mut Manifest, files: &[RenderedFile]) -> Vec<Change> {
+        let changes = plan(files, &manifest.files("test"), false).unwrap();
+        for change in &changes {
+            change.apply().unwrap();
+        }
+        manifest.record("test", &changes);
+        changes
+    }
+
+    fn directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("oam-plan-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn never_prunes_files_of_the_project() {
+        let directory = directory("project");
+        let gitignore = directory.join(".gitignore");
+        let readme = directory.join("README.md");
+        let config = directory.join("config.toml");
+        write(&gitignore, "/target\n").unwrap();
+        write(&readme, "# Pets\n").unwrap();
+
+        let mut manifest = Manifest::default();
+        run(
+            &mut manifest,
+            &[
+                rendered(&gitignore, "/generated\n", WriteMode::Append),
+                rendered(&readme, "# Generated\n", WriteMode::Overwrite),
+                rendered(&config, "", WriteMode::CreateOnce),
+            ],
+        );
+        assert_eq!(read_to_string(&gitignore).unwrap(), "/target\n/generated\n");
+        assert!(manifest.files("test").is_empty());
+
+        let changes = run(&mut manifest, &[]);
+        assert!(changes.is_empty());
+        assert!(gitignore.exists() && readme.exists() && config.exists());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn prunes_files_created_by_the_flavour() {
+        let directory = directory("flavour");
+        let model = directory.join("model.rs");
+
+        let mut manifest = Manifest::default();
+        run(
+            &mut manifest,
+            &[rendered(&model, "struct Pet;\n", WriteMode::Overwrite)],
+        );
+        assert_eq!(manifest.files("test").len(), 1);
+
+        // Still owned once it exists.
+        let changes = run(
+            &mut manifest,
+            &[rendered(&model, "struct Pet {}\n", WriteMode::Overwrite)],
+        );
+        assert_eq!(changes[0].kind, ChangeKind::Modified);
+        assert_eq!(manifest.files("test").len(), 1);
+
+        let changes = run(&mut manifest, &[]);
+        assert_eq!(changes[0].kind, ChangeKind::Deleted);
+        assert!(!model.exists());
+        assert!(manifest.files("test").is_empty());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn overwrites_binary_files_verbatim() {
+        let directory = directory("binary");
+        let icon = directory.join("icon.png");
+        write(&icon, [0x89, b'P', b'N', b'G', 0xff]).unwrap();
+
+        let contents = [0x89, b'P', b'N', b'G', 0x00, 0xfe];
+        let mut manifest = Manifest::default();
+        run(
+            &mut manifest,
+            &[rendered(&icon, &contents[..], WriteMode::Overwrite)],
+        );
+        assert_eq!(read(&icon).unwrap(), contents);
+
+        let append = rendered(&icon, &contents[..], WriteMode::Append);
+        assert!(plan(&[append], &manifest.files("test"), false).is_err());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restores_permissions_of_cached_files() {
+        let directory = directory("permissions");
+        let script = directory.join("build.sh");
+        write(&script, "#!/bin/sh\n").unwrap();
+        set_permissions(&script, 0o644).unwrap();
+
+        let file = RenderedFile {
+            cached: true,
+            permissions: Some(0o755),
+            ..rendered(&script, "#!/bin/sh\n", WriteMode::Overwrite)
+        };
+        let changes = run(&mut Manifest::default(), &[file]);
+        assert_eq!(changes[0].kind, ChangeKind::Modified);
+        assert!(has_permissions(&script, Some(0o755)));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,1168 @@
+use anyhow::{anyhow, Context as _};
+use rayon::prelude::*;
+use std::{
+    cell::RefCell,
+    collections::{hash_map::RandomState, BTreeSet, HashMap},
+    fs::{read, read_to_string},
+    hash::BuildHasher,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tera::{Context, Tera, Value};
+
+use crate::{
+    case,
+    fingerprint::{Fingerprint, Inputs},
+    flavour::{files, partials, Flavour, Template, WriteMode},
+    ir::Ir,
+    language::Language,
+    locate,
+    manifest::{hash, Files},
+    merge::{merge_value, serialize},
+    schema::OpenAPI,
+};
+
+/// A file produced by rendering a template.
+#[derive(Debug)]
+pub struct RenderedFile {
+    /// The path of the file, relative to the project.
+    pub path: PathBuf,
+    /// The rendered contents, or those of an asset, which need not be text.
+    pub contents: Vec<u8>,
+    /// How the file is written if it already exists.
+    pub mode: WriteMode,
+    /// The [Fingerprint] of the inputs the file is rendered from.
+    pub fingerprint: String,
+    /// Whether the file was up to date and not rendered again, in which case its contents are those on disk.
+    pub cached: bool,
+    /// The permission bits the file is given, if any.
+    pub permissions: Option<u32>,
+}
+
+/// Renders the templates of a flavour.
+pub struct Renderer {
+    tera: Tera,
+    language: Arc<Language>,
+    /// The resolved options of the flavour.
+    options: tera::Map<String, Value>,
+    /// The schema file, in which errors locate the item being rendered.
+    spec: Option<PathBuf>,
+}
+
+impl Renderer {
+    /// Loads the templates of a flavour, registered under the name of their input, its [partials]
+    /// and the script of its language, which both receive the resolved `options`.
+    pub fn new(flavour: &Flavour, options: tera::Map<String, Value>) -> anyhow::Result<Self> {
+        let mut renderer = Renderer::base(flavour, options)?;
+        for (index, template) in flavour.templates.iter().enumerate() {
+            renderer
+                .tera
+                .add_template_file(&template.source, Some(&template.input))?;
+            if let Some(when) = &template.when {
+                add_condition(&mut renderer.tera, index, when).with_context(|| {
+                    format!("Invalid when expression of template {}", template.input)
+                })?;
+            }
+        }
+
+        Ok(renderer)
+    }
+
+    /// Loads everything of a flavour but its templates: the partials, the filters and the language.
+    pub fn base(flavour: &Flavour, options: tera::Map<String, Value>) -> anyhow::Result<Self> {
+        let mut tera = Tera::default();
+        // Partials come first, as templates are checked for the macros they import when added.
+        tera.add_template_files(
+            partials(flavour)?
+                .into_iter()
+                .map(|(name, path)| (path, Some(name)))
+                .collect::<Vec<_>>(),
+        )?;
+        register_filters(&mut tera);
+        let language = Arc::new(Language::load(&flavour.language)?.with_options(&options)?);
+        register_language_filters(&mut tera, language.clone());
+
+        Ok(Renderer {
+            tera,
+            language,
+            options,
+            spec: None,
+        })
+    }
+
+    /// The templates, partials and filters registered so far, e.g. for checking templates without rendering them.
+    pub fn tera(&self) -> &Tera {
+        &self.tera
+    }
+
+    /// Adds a template from its contents rather than a file, e.g. one given on the command line.
+    pub fn with_template(mut self, name: &str, contents: &str) -> anyhow::Result<Self> {
+        self.tera.add_raw_template(name, contents)?;
+        Ok(self)
+    }
+
+    /// Sets the file the document is read from, so errors point to the items they occurred for.
+    pub fn with_spec(mut self, path: impl Into<PathBuf>) -> Self {
+        self.spec = Some(path.into());
+        self
+    }
+
+    /// Renders every template of the flavour, once per item of its iteration if it has one.
+    /// Files are rendered in parallel.
+    ///
+    /// Templates receive the document at the top level, the [Ir] as `ir`, the options of the flavour as `options`
+    /// and the name of its language as `language.name`.
+    /// An iteration is a dotted path into the same context, e.g. `components.schemas` or `ir.types`,
+    /// and adds the key of the current item as `entity` and the item itself as `item`.
+    ///
+    /// Files recorded in `cache` with the same fingerprint and unchanged on disk are not rendered again.
+    /// A template may emit several files by starting sections with `{{ file(path="src/pets/mod.rs") }}`,
+    /// in which case its files are always rendered again.
+    ///
+    /// The assets and fragments of the flavour follow the rendered files. Writing a file twice, e.g. from
+    /// two templates with the same output, is an error, as one would silently replace the other.
+    /// Outputs may not leave the project. A template with an empty output renders files without a path,
+    /// which are not written but printed, as by `oam render`.
+    pub fn render(
+        &self,
+        flavour: &Flavour,
+        document: &OpenAPI,
+        ir: &Ir,
+        cache: Option<&Files>,
+    ) -> anyhow::Result<Vec<RenderedFile>> {
+        let global = self.global(document, ir)?;
+        let context = Context::from_value(global.clone())?;
+
+        let mut shared = Vec::new();
+        for (index, template) in flavour.templates.iter().enumerate() {
+            let inputs = self.inputs(index, template);
+            let fingerprint = self.fingerprint(template, &inputs, &global)?;
+            let splits = inputs.functions.contains("file");
+            shared.push((fingerprint, splits));
+        }
+
+        let mut jobs = Vec::new();
+        for ((index, template), (fingerprint, splits)) in
+            flavour.templates.iter().enumerate().zip(&shared)
+        {
+            let Some(iteration) = &template.iteration else {
+                jobs.push(Job {
+                    index,
+                    template,
+                    fingerprint: fingerprint.clone(),
+                    splits: *splits,
+                    item: None,
+                });
+                continue;
+            };
+
+            for (entity, item) in iterate(&global, iteration)? {
+                jobs.push(Job {
+                    index,
+                    template,
+                    fingerprint: fingerprint.clone(),
+                    splits: *splits,
+                    item: Some((entity, item)),
+                });
+            }
+        }
+
+        // Every template receives the whole context. It is copied once per batch of jobs rayon
+        // hands to a thread rather than per file, and each job only replaces the item in it.
+        let total = jobs.len();
+        let results: Vec<(String, Result<Vec<RenderedFile>, String>)> = jobs
+            .into_par_iter()
+            .map_init(
+                || context.clone(),
+                |context, job| {
+                    let template = job.template;
+                    let item = job
+                        .item
+                        .as_ref()
+                        .map(|(entity, item)| (entity.clone(), *item));
+                    let origin = match &item {
+                        Some((entity, _)) => format!("template {} for {entity}", template.input),
+                        None => format!("template {}", template.input),
+                    };
+                    let result = self
+                        .job(job, context, cache)
+                        .map_err(|error| self.describe(template, item, error));
+                    (origin, result)
+                },
+            )
+            .collect();
+
+        // Files keep the order of their templates and items. All of them are rendered before
+        // failing, so every error is reported at once and in the same order.
+        let mut files = Vec::new();
+        let mut errors = Vec::new();
+        for (origin, result) in results {
+            match result {
+                Ok(rendered) => {
+                    files.extend(rendered.into_iter().map(|file| (origin.clone(), file)))
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        match errors.len() {
+            0 => {
+                files.extend(self.assets(flavour, cache)?);
+                files.extend(
+                    self.fragments(flavour, cache)?
+                        .into_iter()
+                        .map(|file| (String::from("the fragments"), file)),
+                );
+                unique(files)
+            }
+            failed => Err(anyhow!(
+                "{failed} of {total} files failed to render\n\n{}",
+                errors.join("\n\n")
+            )),
+        }
+    }
+
+    /// The context every template, and every item of iterated templates, is rendered with, regardless
+    /// of their `when` expression. Filtered to the template with input `template` and the item named `entity`, if given.
+    /// Each context is returned as `{ "template": ..., "entity": ..., "context": ... }`.
+    pub fn contexts(
+        &self,
+        flavour: &Flavour,
+        document: &OpenAPI,
+        ir: &Ir,
+        template: Option<&str>,
+        entity: Option<&str>,
+    ) -> anyhow::Result<Vec<Value>> {
+        let global = self.global(document, ir)?;
+        let mut context = Context::from_value(global.clone())?;
+        let mut contexts = Vec::new();
+        let selected = flavour
+            .templates
+            .iter()
+            .filter(|candidate| template.is_none_or(|input| candidate.input == input));
+        for candidate in selected {
+            let items = match &candidate.iteration {
+                Some(iteration) => iterate(&global, iteration)?
+                    .into_iter()
+                    .map(|(name, item)| (Some(name), Some(item)))
+                    .collect(),
+                None => vec![(None, None)],
+            };
+
+            for (name, item) in items {
+                if entity.is_some() && name.as_deref() != entity {
+                    continue;
+                }
+                set_item(&mut context, name.as_deref().zip(item));
+                contexts.push(serde_json::json!({
+                    "template": candidate.input,
+                    "entity": name,
+                    "context": context.clone().into_json(),
+                }));
+            }
+        }
+
+        Ok(contexts)
+    }
+
+    /// The context shared by all templates: the document, the [Ir] as `ir`, the options of the flavour
+    /// as `options` and the name of its language as `language.name`.
+    fn global(&self, document: &OpenAPI, ir: &Ir) -> anyhow::Result<Value> {
+        let mut global = tera::to_value(document)?;
+        if let Some(global) = global.as_object_mut() {
+            global.insert(String::from("ir"), tera::to_value(ir)?);
+            global.insert(String::from("options"), Value::Object(self.options.clone()));
+            global.insert(
+                String::from("language"),
+                serde_json::json!({ "name": self.language.name }),
+            );
+        }
+        Ok(global)
+    }
+
+    /// Describes an error with the template and line it occurred at, and for iterated templates
+    /// the item being rendered and where the document defines it, e.g.
+    ///
+    /// ```text
+    /// .openapi/flavours/axum/model.tera:12 (approximate line) for components.schemas.Pet at openapi.yaml:42
+    ///   Variable `item.nmae` not found in context while rendering 'model'
+    /// ```
+    ///
+    /// Tera doesn't report lines of rendering errors, so the line is a guess: the first one mentioning
+    /// the identifier the error is about, which is marked as approximate.
+    fn describe(
+        &self,
+        template: &Template,
+        item: Option<(String, &Value)>,
+        error: anyhow::Error,
+    ) -> String {
+        let message = error
+            .chain()
+            .map(ToString::to_string)
+            .filter(|message| !message.starts_with("Failed to render '"))
+            .collect::<Vec<_>>()
+            .join(": ");
+
+        let mut location = template.source.display().to_string();
+        let identifier = message
+            .split('`')
+            .nth(1)
+            .filter(|identifier| !identifier.is_empty());
+        let line = identifier.and_then(|identifier| {
+            let source = read_to_string(&template.source).ok()?;
+            source.lines().position(|line| line.contains(identifier))
+        });
+        if let Some(line) = line {
+            location.push_str(&format!(":{} (approximate line)", line + 1));
+        }
+
+        if let (Some(iteration), Some((entity, item))) = (&template.iteration, item) {
+            location.push_str(&format!(" for {iteration}.{entity}"));
+            let defined = origin(iteration, &entity, item).and_then(|origin| {
+                let spec = self.spec.as_ref()?;
+                let line = locate::line(&read_to_string(spec).ok()?, &origin)?;
+                Some(format!("{}:{line}", spec.display()))
+            });
+            if let Some(defined) = defined {
+                location.push_str(&format!(" at {defined}"));
+            }
+        }
+
+        format!("{location}\n  {message}")
+    }
+
+    /// Renders the files of a job with the global `context`, unless its `when` expression is false.
+    /// The item of the job replaces that of the previous job in the context.
+    fn job(
+        &self,
+        job: Job,
+        context: &mut Context,
+        cache: Option<&Files>,
+    ) -> anyhow::Result<Vec<RenderedFile>> {
+        let Job {
+            index,
+            template,
+            mut fingerprint,
+            splits,
+            item,
+        } = job;
+
+        set_item(
+            context,
+            item.as_ref().map(|(entity, item)| (entity.as_str(), *item)),
+        );
+        let path = match item {
+            None => PathBuf::from(&template.output),
+            Some((entity, item)) => {
+                fingerprint.add(&entity);
+                fingerprint.add_value(Some(item));
+                PathBuf::from(template.output.replace("{entity}", &entity))
+            }
+        };
+        if !self.condition(index, template, context)? {
+            return Ok(Vec::new());
+        }
+        if !inside_project(&path) {
+            return Err(anyhow!(
+                "Output {} is outside the project, as it is absolute or contains ..",
+                path.display()
+            ));
+        }
+
+        // Which files a splitting template emits is only known after rendering it.
+        let cache = cache.filter(|_| !splits);
+        self.files(template, path, fingerprint.finish(), context, cache)
+    }
+
+    /// Renders a template to the file at `path`, unless the file is up to date.
+    /// Sections started with `file(path=...)` are written to files of their own, see [split].
+    fn files(
+        &self,
+        template: &Template,
+        path: PathBuf,
+        fingerprint: String,
+        context: &Context,
+        cache: Option<&Files>,
+    ) -> anyhow::Result<Vec<RenderedFile>> {
+        let file = |path, contents, cached| RenderedFile {
+            path,
+            contents,
+            mode: template.mode,
+            fingerprint: fingerprint.clone(),
+            cached,
+            permissions: None,
+        };
+        if let Some(contents) = cached(&path, &fingerprint, cache) {
+            return Ok(vec![file(path, contents, true)]);
+        }
+
+        SECTIONS.with_borrow_mut(Vec::clear);
+        let contents = self.tera.render(&template.input, context);
+        let sections = SECTIONS.take();
+        Ok(split(path, contents?, &sections)
+            .into_iter()
+            .map(|(path, contents)| file(path, contents.into_bytes(), false))
+            .collect())
+    }
+
+    /// Copies the assets of a flavour byte for byte, every file under an asset directory to the same path
+    /// under its output. Each file comes with the asset it is copied for.
+    fn assets(
+        &self,
+        flavour: &Flavour,
+        cache: Option<&Files>,
+    ) -> anyhow::Result<Vec<(String, RenderedFile)>> {
+        let mut copied = Vec::new();
+        for asset in &flavour.assets {
+            let output = PathBuf::from(self.placeholders(&asset.output)?);
+            let sources = match asset.source.is_dir() {
+                true => files(&asset.source)?
+                    .into_iter()
+                    .map(|(relative, source)| (output.join(relative), source))
+                    .collect(),
+                false => vec![(output, asset.source.clone())],
+            };
+
+            for (path, source) in sources {
+                let contents = read(&source).map_err(|error| {
+                    anyhow!("Could not read asset {}: {error}", source.display())
+                })?;
+                let mut fingerprint = Fingerprint::new();
+                fingerprint.add(&serde_json::to_string(asset)?);
+                fingerprint.add_bytes(&contents);
+                let fingerprint = fingerprint.finish();
+
+                let (contents, cached) = match cached(&path, &fingerprint, cache) {
+                    Some(contents) => (contents, true),
+                    None => (contents, false),
+                };
+                copied.push((
+                    format!("asset {}", asset.input),
+                    RenderedFile {
+                        path,
+                        contents,
+                        mode: asset.mode,
+                        fingerprint,
+                        cached,
+                        permissions: asset.permissions,
+                    },
+                ));
+            }
+        }
+        Ok(copied)
+    }
+
+    /// Writes the fragments of a flavour, combined per output, to be merged into the files of the project.
+    fn fragments(
+        &self,
+        flavour: &Flavour,
+        cache: Option<&Files>,
+    ) -> anyhow::Result<Vec<RenderedFile>> {
+        let mut combined: Vec<(PathBuf, toml::Value)> = Vec::new();
+        // Fragments of the flavour itself come first, so their values are kept.
+        for fragment in flavour.fragments.iter().rev() {
+            let output = PathBuf::from(self.placeholders(&fragment.output)?);
+            let value = toml::Value::Table(fragment.value.clone());
+            match combined
+                .iter_mut()
+                .find(|(existing, _)| *existing == output)
+            {
+                Some((_, existing)) => merge_value(existing, value),
+                None => combined.push((output, value)),
+            }
+        }
+
+        let mut fragments = Vec::new();
+        for (path, value) in combined.into_iter().rev() {
+            let contents = serialize(&path, &value)?.into_bytes();
+            let mut fingerprint = Fingerprint::new();
+            fingerprint.add(&path.display().to_string());
+            fingerprint.add_bytes(&contents);
+            let fingerprint = fingerprint.finish();
+
+            let (contents, cached) = match cached(&path, &fingerprint, cache) {
+                Some(contents) => (contents, true),
+                None => (contents, false),
+            };
+            fragments.push(RenderedFile {
+                path,
+                contents,
+                mode: WriteMode::Merge,
+                fingerprint,
+                cached,
+                permissions: None,
+            });
+        }
+        Ok(fragments)
+    }
+
+    /// Replaces the placeholders of an asset or fragment output with the values of the options they name,
+    /// e.g. `{crate_dir}/build.rs`.
+    fn placeholders(&self, output: &str) -> anyhow::Result<String> {
+        let mut path = output.to_string();
+        for (name, value) in &self.options {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            path = path.replace(&format!("{{{name}}}"), &value);
+        }
+        match path.contains('{') {
+            true => Err(anyhow!(
+                "Output {output} uses a placeholder which is not an option"
+            )),
+            false => Ok(path),
+        }
+    }
+
+    /// What a template and its `when` expression read.
+    fn inputs(&self, index: usize, template: &Template) -> Inputs {
+        let mut inputs = Inputs::of(&self.tera, &template.input);
+        if template.when.is_some() {
+            let when = Inputs::of(&self.tera, &condition_name(index));
+            inputs.variables.extend(when.variables);
+        }
+        inputs
+    }
+
+    /// Fingerprints what every file of a template depends on, except the item it is rendered for.
+    /// Of each variable the template reads, the first two segments are taken into account,
+    /// e.g. all of `ir.types` for `ir.types[item.name].properties`.
+    fn fingerprint(
+        &self,
+        template: &Template,
+        inputs: &Inputs,
+        global: &Value,
+    ) -> anyhow::Result<Fingerprint> {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.add(self.language.script());
+        // Scripts may read any option.
+        fingerprint.add(&serde_json::to_string(&self.options)?);
+        fingerprint.add(&serde_json::to_string(template)?);
+        inputs.add_sources(&self.tera, &mut fingerprint);
+
+        let slices: BTreeSet<String> = inputs
+            .variables
+            .iter()
+            .map(|variable| {
+                variable
+                    .splitn(3, '.')
+                    .take(2)
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .collect();
+        for slice in slices {
+            let value = match slice.split('.').next() {
+                // Added for every item instead.
+                Some("entity" | "item") => continue,
+                Some("__tera_context") => Some(global),
+                _ => tera::dotted_pointer(global, &slice),
+            };
+            fingerprint.add(&slice);
+            fingerprint.add_value(value);
+        }
+
+        Ok(fingerprint)
+    }
+
+    /// Evaluates the `when` expression of a template, if any.
+    fn condition(
+        &self,
+        index: usize,
+        template: &Template,
+        context: &Context,
+    ) -> anyhow::Result<bool> {
+        if template.when.is_none() {
+            return Ok(true);
+        }
+
+        let result = self
+            .tera
+            .render(&condition_name(index), context)
+            .with_context(|| {
+                format!(
+                    "Could not evaluate when expression of template {}",
+                    template.input
+                )
+            })?;
+        Ok(result == "true")
+    }
+}
+
+/// A file to render: a template, and the item of its iteration if it has one.
+struct Job<'a> {
+    index: usize,
+    template: &'a Template,
+    /// The fingerprint of the template, to which the item is added.
+    fingerprint: Fingerprint,
+    /// Whether the template emits several files with `file(path=...)`.
+    splits: bool,
+    item: Option<(String, &'a Value)>,
+}
+
+/// The dotted path of an item in the document, e.g. `components.schemas.Pet`.
+/// Items of the IR carry their own origin, other items are found by their iteration.
+fn origin(iteration: &str, entity: &str, item: &Value) -> Option<String> {
+    if let Some(origin) = item.get("origin").and_then(Value::as_str) {
+        return Some(origin.to_string());
+    }
+    if let (Some(path), Some(method)) = (
+        item.get("path").and_then(Value::as_str),
+        item.get("method").and_then(Value::as_str),
+    ) {
+        return Some(format!("paths.{path}.{method}"));
+    }
+    match iteration.starts_with("ir.") {
+        true => None,
+        false => Some(format!("{iteration}.{entity}")),
+    }
+}
+
+/// Sets the item of an iterated template as `item` and its name as `entity`, or removes them.
+fn set_item(context: &mut Context, item: Option<(&str, &Value)>) {
+    match item {
+        Some((entity, item)) => {
+            context.insert("entity", entity);
+            context.insert("item", item);
+        }
+        None => {
+            context.remove("entity");
+            context.remove("item");
+        }
+    }
+}
+
+/// The contents of the file at `path` if it was generated from the same inputs and not changed since.
+fn cached(path: &Path, fingerprint: &str, cache: Option<&Files>) -> Option<Vec<u8>> {
+    let entry = cache?.get(&path.display().to_string())?;
+    if entry.fingerprint != fingerprint {
+        return None;
+    }
+
+    let contents = read(path).ok()?;
+    (hash(&contents) == entry.hash).then_some(contents)
+}
+
+/// The name the `when` expression of the template at `index` is registered under.
+pub(crate) fn condition_name(index: usize) -> String {
+    format!("@when/{index}")
+}
+
+/// Registers the `when` expression of the template at `index` as a template rendering `true` if it holds.
+pub(crate) fn add_condition(tera: &mut Tera, index: usize, when: &str) -> tera::Result<()> {
+    tera.add_raw_template(
+        &condition_name(index),
+        &format!("{{% if {when} %}}true{{% endif %}}"),
+    )
+}
+
+/// Resolves an iteration path to its items and the entity names they are rendered as.
+/// Maps name their items by key, lists by the `name` of the item or else its index.
+fn iterate<'a>(context: &'a Value, iteration: &str) -> anyhow::Result<Vec<(String, &'a Value)>> {
+    match tera::dotted_pointer(context, iteration) {
+        Some(Value::Object(items)) => Ok(items
+            .iter()
+            .map(|(key, item)| (key.clone(), item))
+            .collect()),
+        Some(Value::Array(items)) => Ok(items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let entity = match item.get("name").and_then(Value::as_str) {
+                    Some(name) => name.to_string(),
+                    None => index.to_string(),
+                };
+                (entity, item)
+            })
+            .collect()),
+        Some(Value::Null) | None => Ok(Vec::new()),
+        Some(_) => Err(anyhow!(
+            "Iteration {iteration} must point to a map or a list"
+        )),
+    }
+}
+
+thread_local! {
+    /// The sections `file(path=...)` started while rendering a template on this thread, as the placeholder
+    /// the call rendered and the path of the section. Paths don't go through the output, so nothing a
+    /// template outputs, e.g. from the document, can start a file.
+    static SECTIONS: RefCell<Vec<(String, PathBuf)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Counts `file(path=...)` calls, so every call renders a different placeholder.
+static CALLS: AtomicU64 = AtomicU64::new(0);
+
+/// Splits rendered output at the placeholders of the `file(path=...)` calls recorded in `sections`.
+/// The output before the first placeholder is the file at `path`, and left out if it is only whitespace.
+/// Each placeholder starts a file at its path, without the line break following the call.
+/// Sections with the same path are joined.
+fn split(
+    path: PathBuf,
+    contents: String,
+    sections: &[(String, PathBuf)],
+) -> Vec<(PathBuf, String)> {
+    let mut starts: Vec<(usize, usize, &PathBuf)> = sections
+        .iter()
+        .flat_map(|(placeholder, path)| {
+            contents
+                .match_indices(placeholder.as_str())
+                .map(move |(start, _)| (start, start + placeholder.len(), path))
+        })
+        .collect();
+    if starts.is_empty() {
+        return vec![(path, contents)];
+    }
+    starts.sort_by_key(|(start, _, _)| *start);
+
+    let mut files: Vec<(PathBuf, String)> = Vec::new();
+    let head = &contents[..starts[0].0];
+    if !head.trim().is_empty() {
+        files.push((path, head.to_string()));
+    }
+    for (index, (_, end, path)) in starts.iter().enumerate() {
+        let next = starts
+            .get(index + 1)
+            .map_or(contents.len(), |(start, _, _)| *start);
+        let section = &contents[*end..next];
+        let section = section
+            .strip_prefix("\r\n")
+            .or_else(|| section.strip_prefix('\n'))
+            .unwrap_or(section);
+        match files.iter_mut().find(|(existing, _)| existing == *path) {
+            Some((_, contents)) => contents.push_str(section),
+            None => files.push(((*path).clone(), section.to_string())),
+        }
+    }
+    files
+}
+
+/// The files of a render without their origins, failing if several write the same path.
+/// Files without a path are printed rather than written, so never clash.
+fn unique(files: Vec<(String, RenderedFile)>) -> anyhow::Result<Vec<RenderedFile>> {
+    let mut origins: HashMap<&Path, &str> = HashMap::new();
+    let mut duplicates = Vec::new();
+    for (origin, file) in &files {
+        if file.path.as_os_str().is_empty() {
+            continue;
+        }
+        if let Some(first) = origins.insert(&file.path, origin) {
+            duplicates.push(format!(
+                "{} is written by both {first} and {origin}",
+                file.path.display()
+            ));
+        }
+    }
+    match duplicates.is_empty() {
+        true => Ok(files.into_iter().map(|(_, file)| file).collect()),
+        false => Err(anyhow!("{}", duplicates.join("\n"))),
+    }
+}
+
+/// Whether a path relative to the project stays inside it, i.e. is not absolute and has no `..`.
+fn inside_project(path: &Path) -> bool {
+    !path.has_root()
+        && !path
+            .components()
+            .any(|component| matches!(component, Component::ParentDir | Component::Prefix(_)))
+}
+
+/// The `file(path=...)` function, which starts a section written to a file of its own, see [split].
+/// The path is relative to the project and may not leave it.
+fn file(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let path =
+        match args.get("path").and_then(Value::as_str) {
+            Some(path) if !path.is_empty() => Path::new(path),
+            _ => return Err(tera::Error::msg(
+                "The file function expects a non-empty string path, e.g. file(path=\"src/mod.rs\")",
+            )),
+        };
+    if !inside_project(path) {
+        return Err(tera::Error::msg(format!(
+            "The file function expects a path inside the project, but {} is absolute or contains ..",
+            path.display()
+        )));
+    }
+
+    let call = CALLS.fetch_add(1, Ordering::Relaxed);
+    // Randomly keyed, so the output can't contain a placeholder by chance.
+    let placeholder = format!("oam-file-{call}-{:016x}", RandomState::new().hash_one(call));
+    SECTIONS.with_borrow_mut(|sections| sections.push((placeholder.clone(), path.to_path_buf())));
+    Ok(Value::String(placeholder))
+}
+
+/// Registers the naming filters, e.g. `{{ entity | snake_case }}`, and the `file` function.
+fn register_filters(tera: &mut Tera) {
+    tera.register_function("file", file);
+
+    let filters: [(&'static str, Convert); 8] = [
+        ("pascal_case", case::pascal_case),
+        ("camel_case", case::camel_case),
+        ("snake_case", case::snake_case),
+        ("screaming_snake_case", case::screaming_snake_case),
+        ("kebab_case", case::kebab_case),
+        ("pluralize", case::pluralize),
+        ("singularize", case::singularize),
+        ("sanitize_identifier", case::sanitize_identifier),
+    ];
+
+    for (name, convert) in filters {
+        tera.register_filter(name, string_filter(name, convert));
+    }
+}
+
+/// Registers the functions of the language script as filters, e.g. `{{ property.schema | type }}`.
+fn register_language_filters(tera: &mut Tera, language: Arc<Language>) {
+    let filters = [
+        ("type", "map_type"),
+        ("escape_reserved", "escape_reserved"),
+        ("type_name", "type_name"),
+        ("field_name", "field_name"),
+    ];
+
+    for (name, function) in filters {
+        let language = language.clone();
+        tera.register_filter(name, move |value: &Value, _: &HashMap<String, Value>| {
+            let argument = rhai::serde::to_dynamic(value)
+                .map_err(|error| tera::Error::msg(error.to_string()))?;
+            language
+                .call(function, argument)
+                .map(Value::String)
+                .map_err(|error| tera::Error::msg(error.to_string()))
+        });
+    }
+}
+
+/// Converts one name into another.
+type Convert = fn(&str) -> String;
+
+fn string_filter(name: &'static str, convert: Convert) -> impl tera::Filter {
+    move |value: &Value, _: &HashMap<String, Value>| match value.as_str() {
+        Some(value) => Ok(Value::String(convert(value))),
+        None => Err(tera::Error::msg(format!(
+            "Filter `{name}` expected a string but got {value}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    const DOCUMENT: &str = r#"
+openapi: 3.1.0
+info: { title: Pets, version: "1" }
+paths:
+  /pets: {}
+components:
+  schemas:
+    Cat: { type: object, properties: { lives: { type: integer } } }
+    Dog: { type: object, properties: { good: { type: boolean } } }
+"#;
+
+    fn flavour(templates: Vec<Template>) -> Flavour {
+        Flavour {
+            name: String::from("test"),
+            version: None,
+            language: String::from("rust"),
+            templates,
+            assets: Vec::new(),
+            fragments: Vec::new(),
+            options: BTreeMap::new(),
+            chain: Vec::new(),
+        }
+    }
+
+    fn template(input: &str, output: &str, iteration: Option<&str>) -> Template {
+        Template {
+            input: input.to_string(),
+            output: output.to_string(),
+            iteration: iteration.map(str::to_string),
+            mode: WriteMode::Overwrite,
+            when: None,
+            source: PathBuf::from(input),
+        }
+    }
+
+    fn try_render(
+        flavour: &Flavour,
+        templates: &[(&str, &str)],
+    ) -> anyhow::Result<Vec<(String, String)>> {
+        let document: OpenAPI = serde_yaml::from_str(DOCUMENT).unwrap();
+        let ir = Ir::build(&document).unwrap();
+        let mut renderer = Renderer::base(flavour, Default::default()).unwrap();
+        for (name, contents) in templates {
+            renderer = renderer.with_template(name, contents).unwrap();
+        }
+        for (index, template) in flavour.templates.iter().enumerate() {
+            if let Some(when) = &template.when {
+                add_condition(&mut renderer.tera, index, when).unwrap();
+            }
+        }
+        Ok(renderer
+            .render(flavour, &document, &ir, None)?
+            .into_iter()
+            .map(|file| {
+                (
+                    file.path.display().to_string(),
+                    String::from_utf8(file.contents).unwrap(),
+                )
+            })
+            .collect())
+    }
+
+    fn render(flavour: &Flavour, templates: &[(&str, &str)]) -> Vec<(String, String)> {
+        try_render(flavour, templates).unwrap()
+    }
+
+    #[test]
+    fn includes_read_the_whole_context() {
+        let flavour = flavour(vec![template("model", "{entity}.rs", Some("ir.types"))]);
+        let files = render(
+            &flavour,
+            &[
+                ("header", "// {{ info.title }} {{ info.version }}\n"),
+                ("model", r#"{% include "header" %}struct {{ entity }};"#),
+            ],
+        );
+        assert_eq!(
+            files,
+            [
+                (
+                    String::from("Cat.rs"),
+                    String::from("// Pets 1\nstruct Cat;")
+                ),
+                (
+                    String::from("Dog.rs"),
+                    String::from("// Pets 1\nstruct Dog;")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn macros_and_lookups_read_the_whole_context() {
+        let flavour = flavour(vec![
+            template("model", "{entity}.rs", Some("ir.types")),
+            template("lib", "lib.rs", None),
+        ]);
+        let files = render(
+            &flavour,
+            &[
+                (
+                    "macros",
+                    "{% macro fields(schema) %}{{ schema.properties | map(attribute='name') | join(sep=',') }}{% endmacro %}",
+                ),
+                (
+                    "model",
+                    r#"{% import "macros" as macros %}{% set section = "types" %}{{ macros::fields(schema=ir[section][entity].schema) }}"#,
+                ),
+                ("lib", "{{ entity | default(value='none') }}"),
+            ],
+        );
+        assert_eq!(
+            files,
+            [
+                (String::from("Cat.rs"), String::from("lives")),
+                (String::from("Dog.rs"), String::from("good")),
+                // The item of an iterated template doesn't leak into the next one.
+                (String::from("lib.rs"), String::from("none")),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_files_at_file_calls() {
+        let flavour = flavour(vec![template("models", "models.rs", None)]);
+        let files = render(
+            &flavour,
+            &[(
+                "models",
+                "// models\n{% for name, schema in components.schemas %}{{ file(path=name ~ \".rs\") }}\nstruct {{ name }}; // \u{1e}{{ name }}.txt\u{1e}\n{% endfor %}{{ file(path=\"Cat.rs\") }}\nimpl Cat {}\n",
+            )],
+        );
+        assert_eq!(
+            files,
+            [
+                (String::from("models.rs"), String::from("// models\n")),
+                (
+                    String::from("Cat.rs"),
+                    String::from("struct Cat; // \u{1e}Cat.txt\u{1e}\nimpl Cat {}\n")
+                ),
+                (
+                    String::from("Dog.rs"),
+                    String::from("struct Dog; // \u{1e}Dog.txt\u{1e}\n")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_file_paths_outside_the_project() {
+        let flavour = flavour(vec![template("model", "model.rs", None)]);
+        for path in ["/etc/passwd", "../model.rs", "src/../../model.rs"] {
+            let template = format!("{{{{ file(path=\"{path}\") }}}}");
+            let error = try_render(&flavour, &[("model", &template)]).unwrap_err();
+            assert!(
+                format!("{error:#}").contains("inside the project"),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_outputs_outside_the_project() {
+        for (iteration, output) in [("paths", "{entity}.rs"), ("ir.types", "../{entity}.rs")] {
+            let flavour = flavour(vec![template("model", output, Some(iteration))]);
+            let error = try_render(&flavour, &[("model", "")]).unwrap_err();
+            assert!(
+                format!("{error:#}").contains("is outside the project"),
+                "{output}"
+            );
+        }
+    }
+
+    #[test]
+    fn prints_files_without_a_path() {
+        let flavour = flavour(vec![template("model", "", Some("ir.types"))]);
+        let files = render(&flavour, &[("model", "{{ entity }}")]);
+        assert_eq!(
+            files,
+            [
+                (String::new(), String::from("Cat")),
+                (String::new(), String::from("Dog"))
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_files_written_twice() {
+        let flavour = flavour(vec![
+            template("model", "{entity}.rs", Some("ir.types")),
+            template("cat", "Cat.rs", None),
+        ]);
+        let error = try_render(&flavour, &[("model", ""), ("cat", "")]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Cat.rs is written by both template model for Cat and template cat"
+        );
+    }
+
+    #[test]
+    fn renders_templates_whose_condition_holds() {
+        let mut kept = template("kept", "kept.rs", None);
+        kept.when = Some(String::from("info.title == 'Pets'"));
+        let mut skipped = template("skipped", "skipped.rs", None);
+        skipped.when = Some(String::from("options.serde | default(value=false)"));
+        let flavour = flavour(vec![kept, skipped]);
+        let files = render(&flavour, &[("kept", "kept"), ("skipped", "skipped")]);
+        assert_eq!(files, [(String::from("kept.rs"), String::from("kept"))]);
+    }
+
+    #[test]
+    fn evaluates_conditions_per_item() {
+        let mut model = template("model", "{entity}.rs", Some("components.schemas"));
+        model.when = Some(String::from("item.properties.lives"));
+        let flavour = flavour(vec![model]);
+        let files = render(&flavour, &[("model", "struct {{ entity }};")]);
+        assert_eq!(
+            files,
+            [(String::from("Cat.rs"), String::from("struct Cat;"))]
+        );
+    }
+
+    #[test]
+    fn rejects_conditions_which_fail_to_parse() {
+        let directory =
+            std::env::temp_dir().join(format!("oam-renderer-when-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut model = template("model", "model.rs", None);
+        model.source = directory.join("model.tera");
+        std::fs::write(&model.source, "").unwrap();
+        model.when = Some(String::from("item.properties ||"));
+
+        let error = match Renderer::new(&flavour(vec![model]), Default::default()) {
+            Ok(_) => panic!("the when expression parsed"),
+            Err(error) => error,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Invalid when expression of template model"
+        );
+        assert!(format!("{error:#}").contains("'@when/0'"));
+    }
+
+    /// The template and entity of every context `contexts` returns for the filters.
+    fn contexts(input: Option<&str>, entity: Option<&str>) -> Vec<(String, Value)> {
+        let flavour = flavour(vec![
+            template("model", "{entity}.rs", Some("components.schemas")),
+            template("lib", "lib.rs", None),
+        ]);
+        let document: OpenAPI = serde_yaml::from_str(DOCUMENT).unwrap();
+        let ir = Ir::build(&document).unwrap();
+        Renderer::base(&flavour, Default::default())
+            .unwrap()
+            .contexts(&flavour, &document, &ir, input, entity)
+            .unwrap()
+            .into_iter()
+            .map(|context| {
+                (
+                    context["template"].as_str().unwrap().to_string(),
+                    context["entity"].clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn filters_contexts_by_template_and_entity() {
+        let model = String::from("model");
+        let lib = String::from("lib");
+        assert_eq!(
+            contexts(None, None),
+            [
+                (model.clone(), Value::from("Cat")),
+                (model.clone(), Value::from("Dog")),
+                (lib.clone(), Value::Null),
+            ]
+        );
+        assert_eq!(contexts(Some("lib"), None), [(lib, Value::Null)]);
+        assert_eq!(
+            contexts(Some("model"), Some("Dog")),
+            [(model.clone(), Value::from("Dog"))]
+        );
+        assert_eq!(contexts(None, Some("Cat")), [(model, Value::from("Cat"))]);
+    }
+
+    #[test]
+    fn returns_no_contexts_if_nothing_matches() {
+        assert!(contexts(Some("missing"), None).is_empty());
+        assert!(contexts(None, Some("Bird")).is_empty());
+        assert!(contexts(Some("lib"), Some("Cat")).is_empty());
+    }
+
+    #[test]
+    fn dumps_the_context_templates_are_rendered_with() {
+        let flavour = flavour(vec![template(
+            "model",
+            "{entity}.json",
+            Some("components.schemas"),
+        )]);
+        let document: OpenAPI = serde_yaml::from_str(DOCUMENT).unwrap();
+        let ir = Ir::build(&document).unwrap();
+        let dumped = Renderer::base(&flavour, Default::default())
+            .unwrap()
+            .contexts(&flavour, &document, &ir, None, None)
+            .unwrap();
+        let rendered = render(&flavour, &[("model", "{{ __tera_context }}")]);
+
+        assert_eq!(dumped.len(), rendered.len());
+        for (dumped, (_, rendered)) in dumped.iter().zip(rendered) {
+            let rendered: Value = serde_json::from_str(&rendered).unwrap();
+            assert_eq!(dumped["context"], rendered);
+        }
+    }
+}
@@ -2,6 +2,9 @@ use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs::File};
 
+/// A map of possible out-of band callbacks, keyed by a runtime expression.
+pub type CallbackObject = HashMap<String, ReferenceOr<PathItemObject>>;
+
 /// A self-contained or composite resource which defines or describes an API or elements of an API.
 /// The OpenAPI document MUST contain at least one [paths] field, a [components] field or a [webhooks] field.
 /// An OpenAPI document uses and conforms to the OpenAPI Specification.
@@ -122,8 +125,7 @@ pub struct OperationObject {
     /// The list of possible responses as they are returned from executing this operation.
     pub responses: Option<HashMap<String, ReferenceOr<ResponseObject>>>,
     /// A map of possible out-of band callbacks related to the parent operation.
-    pub callbacks:
-        Option<HashMap<String, ReferenceOr<HashMap<String, ReferenceOr<PathItemObject>>>>>,
+    pub callbacks: Option<HashMap<String, ReferenceOr<CallbackObject>>>,
     /// Declares this operation to be deprecated.
     pub deprecated: Option<bool>,
     /// A declaration of which security mechanisms can be used for this operation.
@@ -184,10 +186,65 @@ pub struct MediaTypeObject {
 /// The Schema Object allows the definition of input and output data types.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SchemaObject {
+    /// A reference to a schema defined elsewhere, e.g. `#/components/schemas/Pet`.
+    #[serde(rename = "$ref")]
+    pub reference: Option<String>,
+    /// A short title of the schema.
+    pub title: Option<String>,
+    /// A description of the schema.
+    pub description: Option<String>,
+    /// The data type, either a single type or a list of types.
+    pub r#type: Option<SchemaType>,
+    /// Refines the data type, e.g. `int64` or `uuid`.
+    pub format: Option<String>,
+    /// Declares whether `null` is an allowed value. Superseded by a `null` type in OpenAPI 3.1.
+    pub nullable: Option<bool>,
+    /// The names of the properties which MUST be present.
+    pub required: Option<Vec<String>>,
+    /// The properties of an object, keyed by name.
+    pub properties: Option<HashMap<String, SchemaObject>>,
+    /// The schema of the values of an object which are not listed in `properties`.
+    #[serde(rename = "additionalProperties")]
+    pub additional_properties: Option<AdditionalProperties>,
+    /// The schema of the items of an array.
+    pub items: Option<Box<SchemaObject>>,
+    /// The allowed values.
+    pub r#enum: Option<Vec<serde_yaml::Value>>,
+    /// The value MUST be valid against exactly one of the given schemas.
+    #[serde(rename = "oneOf")]
+    pub one_of: Option<Vec<SchemaObject>>,
+    /// The value MUST be valid against at least one of the given schemas.
+    #[serde(rename = "anyOf")]
+    pub any_of: Option<Vec<SchemaObject>>,
+    /// The value MUST be valid against all of the given schemas.
+    #[serde(rename = "allOf")]
+    pub all_of: Option<Vec<SchemaObject>>,
+    /// Declares this schema to be deprecated.
+    pub deprecated: Option<bool>,
     pub discriminator: Option<DiscriminatorObject>,
     pub xml: Option<XMLObject>,
+    #[serde(rename = "externalDocs")]
     pub external_docs: Option<ExternalDocumentationObject>,
-    pub example: Option<String>,
+    pub example: Option<serde_yaml::Value>,
+    /// Overrides the name generators use for this schema.
+    #[serde(rename = "x-oam-name")]
+    pub oam_name: Option<String>,
+}
+
+/// The `type` of a schema. OpenAPI 3.1 allows a list of types, e.g. `[string, "null"]`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum SchemaType {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+/// The `additionalProperties` of a schema, either a flag or the schema of the values.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum AdditionalProperties {
+    Allowed(bool),
+    Schema(Box<SchemaObject>),
 }
 
 /// When request bodies or response payloads may be one of a number of different schemas,
@@ -198,6 +255,7 @@ pub struct DiscriminatorObject {
     #[serde(rename = "propertyName")]
     pub property_name: String,
     /// An object to hold mappings between payload values and model names or references.
+    #[serde(default)]
     pub mapping: HashMap<String, String>,
 }
 
@@ -350,8 +408,7 @@ pub struct ComponentsObject {
     #[serde(rename = "securitySchemes")]
     pub security_schemes: Option<HashMap<String, ReferenceOr<SecurityScheme>>>,
     pub links: Option<HashMap<String, ReferenceOr<LinkObject>>>,
-    pub callbacks:
-        Option<HashMap<String, ReferenceOr<HashMap<String, ReferenceOr<PathItemObject>>>>>,
+    pub callbacks: Option<HashMap<String, ReferenceOr<CallbackObject>>>,
     #[serde(rename = "pathItems")]
     pub path_items: Option<HashMap<String, ReferenceOr<PathItemObject>>>,
 }
@@ -388,7 +445,7 @@ pub enum SecurityScheme {
     #[serde(rename = "oauth2")]
     OAuth2 {
         /// An object containing configuration information for the flow types supported.
-        flows: OAuthFlowsObject,
+        flows: Box<OAuthFlowsObject>,
         /// A description for security scheme.
         description: Option<String>,
     },
@@ -480,3 +537,22 @@ impl OpenAPI {
         serde_yaml::from_reader(file).map_err(|_| anyhow!("Could not parse file"))
     }
 }
+
+impl PathItemObject {
+    /// Returns the operations of the path item together with their lowercase method, in a fixed order.
+    pub fn operations(&self) -> Vec<(&'static str, &OperationObject)> {
+        [
+            ("get", &self.get),
+            ("put", &self.put),
+            ("post", &self.post),
+            ("delete", &self.delete),
+            ("options", &self.options),
+            ("head", &self.head),
+            ("patch", &self.patch),
+            ("trace", &self.trace),
+        ]
+        .into_iter()
+        .filter_map(|(method, operation)| operation.as_ref().map(|operation| (method, operation)))
+        .collect()
+    }
+}
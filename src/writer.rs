@@ -0,0 +1,230 @@
+use anyhow::anyhow;
+use std::{collections::HashMap, str::from_utf8};
+
+use crate::{flavour::WriteMode, merge::merge, renderer::RenderedFile};
+
+/// The contents a rendered file should have on disk.
+pub struct Output {
+    /// The new contents of the file, or `None` if the existing file is left as is.
+    pub contents: Option<Vec<u8>>,
+    pub warnings: Vec<String>,
+}
+
+/// Decides what to write for a rendered file given the existing file, according to its [WriteMode].
+///
+/// Overwritten files keep their protected regions. A protected region is delimited by lines
+/// containing `oam:begin custom(<name>)` and `oam:end`, usually placed in comments by the template:
+///
+/// ```text
+/// // oam:begin custom(imports)
+/// use crate::extra;
+/// // oam:end
+/// ```
+///
+/// A warning is returned for every region which was dropped because the template no longer renders it.
+/// Files which are not UTF-8 text, such as binary assets, are overwritten as they are, and cannot be
+/// appended to or merged into.
+pub fn output(file: &RenderedFile, existing: Option<&[u8]>) -> anyhow::Result<Output> {
+    let Some(existing) = existing else {
+        return Ok(Output {
+            contents: Some(file.contents.clone()),
+            warnings: Vec::new(),
+        });
+    };
+
+    let mut warnings = Vec::new();
+    let text = from_utf8(existing).ok().zip(from_utf8(&file.contents).ok());
+    let contents = match (file.mode, text) {
+        (WriteMode::Overwrite, None) => Some(file.contents.clone()),
+        (WriteMode::Overwrite, Some((existing, rendered))) => {
+            let (merged, dropped) = keep_regions(existing, rendered);
+            for name in dropped {
+                warnings.push(format!(
+                    "Protected region {name} in {} was dropped because the template no longer contains it",
+                    file.path.display()
+                ));
+            }
+            Some(merged.into_bytes())
+        }
+        (WriteMode::CreateOnce, _) => None,
+        (WriteMode::Append | WriteMode::Merge, None) => {
+            return Err(anyhow!(
+                "Cannot {} {} as it is not UTF-8 text",
+                match file.mode {
+                    WriteMode::Append => "append to",
+                    _ => "merge into",
+                },
+                file.path.display()
+            ))
+        }
+        (WriteMode::Append, Some((existing, rendered))) if existing.contains(rendered) => None,
+        (WriteMode::Append, Some((existing, rendered))) => {
+            let separator = match existing.is_empty() || existing.ends_with('\n') {
+                true => "",
+                false => "\n",
+            };
+            Some(format!("{existing}{separator}{rendered}").into_bytes())
+        }
+        (WriteMode::Merge, Some((existing, rendered))) => {
+            Some(merge(&file.path, existing, rendered)?.into_bytes())
+        }
+    };
+
+    Ok(Output { contents, warnings })
+}
+
+/// Replaces the protected regions of `rendered` with those of the same name in `existing`.
+/// Returns the merged contents and the names of the regions of `existing` missing in `rendered`.
+pub fn keep_regions(existing: &str, rendered: &str) -> (String, Vec<String>) {
+    let mut kept = regions(existing);
+    let terminated = regions(rendered);
+    let mut merged = String::with_capacity(rendered.len());
+    let mut current: Option<String> = None;
+
+    for line in rendered.split_inclusive('\n') {
+        match &current {
+            Some(name) if is_end(line) => {
+                if let Some(body) = kept.remove(name.as_str()) {
+                    merged.push_str(&body);
+                }
+                merged.push_str(line);
+                current = None;
+            }
+            // Rendered region bodies are defaults, used only if the existing file has no such region.
+            Some(name) if !kept.contains_key(name.as_str()) => merged.push_str(line),
+            Some(_) => {}
+            None => {
+                current = begin(line).filter(|name| terminated.contains_key(name));
+                merged.push_str(line);
+            }
+        }
+    }
+
+    let mut dropped: Vec<String> = kept.into_keys().collect();
+    dropped.sort();
+
+    (merged, dropped)
+}
+
+/// Extracts the bodies of all terminated protected regions, keyed by name.
+fn regions(contents: &str) -> HashMap<String, String> {
+    let mut regions = HashMap::new();
+    let mut current: Option<(String, String)> = None;
+
+    for line in contents.split_inclusive('\n') {
+        match current.as_mut() {
+            Some(_) if is_end(line) => {
+                if let Some((name, body)) = current.take() {
+                    regions.insert(name, body);
+                }
+            }
+            Some((_, body)) => body.push_str(line),
+            None => current = begin(line).map(|name| (name, String::new())),
+        }
+    }
+
+    regions
+}
+
+/// The name of the region a line begins, if any.
+fn begin(line: &str) -> Option<String> {
+    let (_, rest) = line.split_once("oam:begin custom(")?;
+    let (name, _) = rest.split_once(')')?;
+    Some(name.trim().to_string())
+}
+
+/// Whether a line ends a region, i.e. holds `oam:end` followed by nothing but a comment terminator
+/// such as `*/` or `-->`.
+fn is_end(line: &str) -> bool {
+    line.split_once("oam:end").is_some_and(|(_, rest)| {
+        rest.trim()
+            .chars()
+            .all(|character| character.is_ascii_punctuation() && character != '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_existing_regions() {
+        let existing = "\
+// oam:begin custom(imports)
+use crate::extra;
+// oam:end
+struct Pet;
+";
+        let rendered = "\
+// generated
+// oam:begin custom(imports)
+// oam:end
+struct Pet {}
+";
+        let (merged, dropped) = keep_regions(existing, rendered);
+        assert_eq!(
+            merged,
+            "\
+// generated
+// oam:begin custom(imports)
+use crate::extra;
+// oam:end
+struct Pet {}
+"
+        );
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn renders_default_of_new_regions() {
+        let rendered = "\
+# oam:begin custom(extra)
+default = true
+# oam:end
+";
+        let (merged, dropped) = keep_regions("", rendered);
+        assert_eq!(merged, rendered);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn reports_dropped_regions() {
+        let existing = "\
+<!-- oam:begin custom(b) -->
+b
+<!-- oam:end -->
+<!-- oam:begin custom(a) -->
+a
+<!-- oam:end -->
+";
+        let (merged, dropped) = keep_regions(existing, "nothing\n");
+        assert_eq!(merged, "nothing\n");
+        assert_eq!(dropped, ["a", "b"]);
+    }
+
+    #[test]
+    fn extracts_terminated_regions() {
+        let contents = "\
+/* oam:begin custom( first ) */
+one
+two
+/* oam:end */
+// oam:begin custom(unterminated)
+three
+";
+        let regions = regions(contents);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions["first"], "one\ntwo\n");
+    }
+
+    #[test]
+    fn matches_exact_end_marker() {
+        assert!(is_end("// oam:end\n"));
+        assert!(is_end("  /* oam:end */"));
+        assert!(is_end("<!-- oam:end -->"));
+        assert!(is_end("{# oam:end #}"));
+        assert!(!is_end("// oam:endpoint"));
+        assert!(!is_end("// oam:end of the imports"));
+        assert!(!is_end("// oam:begin custom(end)"));
+    }
+}